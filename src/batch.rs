@@ -0,0 +1,146 @@
+use std::collections::HashSet;
+
+use crate::error::{Error, Result};
+use crate::ledger::Ledger;
+use crate::mapping::{Mapping, MappingChecker};
+use crate::report::{Entry, Outcome, Report};
+use crate::source::TxSource;
+use crate::types::H256;
+
+/// Verify the mapping transactions of the hash file, one hash per line, and report the outcome of each line.
+///
+/// The accepted mappings are recorded to the ledger.
+/// The failure of a line never stops the verification of the rest.
+pub struct BatchVerifier<'a, S> {
+    pub checker: MappingChecker<'a, S>,
+    pub ledger: &'a mut Ledger,
+}
+
+impl<'a, S: TxSource> BatchVerifier<'a, S> {
+    pub fn verify(&mut self, hashes: &str) -> Report {
+        let mut report = Report::default();
+        // The ledger only knows the mappings of the previous runs.
+        let mut seen_txs = HashSet::new();
+        let mut seen_addresses = HashSet::new();
+        for line in hashes.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut entry = self.verify_hash(line);
+            if let Some(from) = entry.from {
+                if !seen_txs.insert(line.trim_start_matches("0x").to_lowercase()) || !seen_addresses.insert(from) {
+                    entry.outcome = Outcome::Duplicate;
+                    entry.message = Some(format!("Ethereum address {:?} or tx appears more than once", from));
+                }
+            }
+            match entry.outcome {
+                Outcome::Ok => info!("Ethereum tx {} is valid: {:?}", line, entry),
+                _ => warn!("Ethereum tx {} is invalid: {:?}", line, entry),
+            }
+            report.push(entry);
+        }
+        report
+    }
+
+    fn verify_hash(&mut self, line: &str) -> Entry {
+        let mapping = match self.check_hash(line) {
+            Ok(mapping) => mapping,
+            Err(err) => return Entry::failed(line.to_string(), Outcome::from_error(&err), err.to_string()),
+        };
+        let mut entry = Entry::ok(line.to_string(), &mapping.who, mapping.from);
+        if !self.checker.owners.is_owner(&mapping.from) {
+            entry.outcome = Outcome::NotOwner;
+            entry.message = Some(format!("Ethereum address {:?} is not a DOT owner", mapping.from));
+            return entry;
+        }
+        match self.checker.accept(self.ledger, &mapping) {
+            Ok(amount) => entry.amount = Some(amount),
+            Err(err) => {
+                entry.outcome = Outcome::from_error(&err);
+                entry.message = Some(err.to_string());
+            }
+        }
+        entry
+    }
+
+    fn check_hash(&self, line: &str) -> Result<Mapping> {
+        let bytes = hex::decode(line.trim_start_matches("0x"))?;
+        if bytes.len() != 32 {
+            return Err(Error::Hex(hex::FromHexError::InvalidStringLength));
+        }
+        self.checker.check_hash(H256::from_slice(&bytes), self.ledger)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::payout::{PayoutCalculator, Ratio};
+    use crate::snapshot::{OwnerSnapshot, SnapshotFormat};
+    use crate::source::FixtureSource;
+    use crate::verify::ReceiptCache;
+    use std::fs;
+
+    #[test]
+    fn test_verify_batch() {
+        let path = std::env::temp_dir().join(format!("sdot-ledger-{}-verify-batch.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let mut ledger = Ledger::open(path).unwrap();
+        let source = FixtureSource::new(std::env::temp_dir());
+        let mut verifier = BatchVerifier {
+            checker: MappingChecker {
+                source: &source,
+                owners: &OwnerSnapshot::default(),
+                calculator: &PayoutCalculator::new(Ratio::new(1, 1), 3, 3),
+                min_confirmations: 12,
+                receipts: &ReceiptCache::default(),
+            },
+            ledger: &mut ledger,
+        };
+
+        let hashes = "0x33469b22e9f636356c4160a87eb19df52b7412e8eac32a4a55ffe88ea8350788\n\n# comment\nxyz\n0x1234\n";
+        let report = verifier.verify(hashes);
+        let outcomes = report.entries.iter().map(|entry| entry.outcome).collect::<Vec<_>>();
+        assert_eq!(outcomes, vec![Outcome::NotFound, Outcome::InvalidData, Outcome::InvalidData]);
+        assert_eq!(report.entries[1].hash, "xyz");
+        assert!(report.has_failures());
+    }
+
+    #[test]
+    fn test_verify_batch_fixtures() {
+        let path = std::env::temp_dir().join(format!("sdot-ledger-{}-verify-batch-fixtures.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let mut ledger = Ledger::open(path.clone()).unwrap();
+        let source = FixtureSource::new(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures"));
+        let (owners, _) =
+            OwnerSnapshot::parse("9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f,1500\n", SnapshotFormat::Csv).unwrap();
+        let calculator = PayoutCalculator::new(Ratio::new(1, 1), 3, 3);
+        let mut verifier = BatchVerifier {
+            checker: MappingChecker {
+                source: &source,
+                owners: &owners,
+                calculator: &calculator,
+                min_confirmations: 1,
+                receipts: &ReceiptCache::default(),
+            },
+            ledger: &mut ledger,
+        };
+
+        let alice = "0xece0a27a08d6a1765d2331e596a42da43bbb1acb2293fc818d0259a56b0a0166";
+        let bob = "0xa29417602258a521a722afdaaefc21a4b184ba76a3964c5f2850645dfbe56102";
+        let report = verifier.verify(&format!("{}\n{}\n{}\n", alice, alice, bob));
+        let outcomes = report.entries.iter().map(|entry| entry.outcome).collect::<Vec<_>>();
+        assert_eq!(outcomes, vec![Outcome::Ok, Outcome::Duplicate, Outcome::NotOwner]);
+        assert_eq!(report.entries[0].amount, Some(1500));
+
+        // mapped by the previous run
+        let report = verifier.verify(alice);
+        assert_eq!(report.entries[0].outcome, Outcome::Duplicate);
+        let ledger = Ledger::open(path.clone()).unwrap();
+        assert!(ledger.get(&"9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f".parse().unwrap()).is_some());
+        assert!(ledger.get(&"b595b18c88b1f651ca387489067f855b5c8e6720".parse().unwrap()).is_none());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
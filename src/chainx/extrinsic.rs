@@ -0,0 +1,133 @@
+use parity_codec::{Compact, Encode};
+use substrate_primitives::ed25519::Pair;
+use substrate_primitives::hashing::blake2_256;
+
+use chainx_primitives::{AccountId, Balance, Index};
+
+use crate::types::H256;
+
+/// The token name of SDOT in the `XAssets` module.
+pub const SDOT_TOKEN: &[u8] = b"SDOT";
+
+/// The version byte of the signed extrinsic: the signed flag and the extrinsic format version 1.
+const SIGNED_VERSION: u8 = 0b1000_0001;
+/// The prefix of the account id encoded as `Address`.
+const ACCOUNT_ID_PREFIX: u8 = 0xff;
+/// The encoded immortal era.
+const IMMORTAL_ERA: u8 = 0;
+/// The ChainX transaction fee multiplier, 1 is the normal fee.
+const ACCELERATION: u32 = 1;
+/// The payload longer than this is hashed before signing.
+const MAX_PAYLOAD_LEN: usize = 256;
+
+/// `XAssets::transfer(dest, token, #[compact] value, memo)` of the ChainX runtime.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TransferCall {
+    /// The module index and the call index of `XAssets::transfer` in the runtime.
+    pub call_index: [u8; 2],
+    pub dest: AccountId,
+    pub token: Vec<u8>,
+    pub value: Balance,
+    pub memo: Vec<u8>,
+}
+
+impl TransferCall {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut encoded = self.call_index.to_vec();
+        encode_address(self.dest.as_ref(), &mut encoded);
+        self.token.encode_to(&mut encoded);
+        Compact(self.value).encode_to(&mut encoded);
+        self.memo.encode_to(&mut encoded);
+        encoded
+    }
+}
+
+fn encode_address(account: &[u8], dest: &mut Vec<u8>) {
+    dest.push(ACCOUNT_ID_PREFIX);
+    dest.extend_from_slice(account);
+}
+
+/// Sign the call and return the encoded extrinsic, with the length prefix.
+///
+/// The extrinsic is immortal, so the signed payload `(nonce, call, era, genesis hash, acceleration)`
+/// is checked against the genesis hash.
+pub fn sign_extrinsic(call: &TransferCall, signer: &Pair, nonce: Index, genesis_hash: &H256) -> Vec<u8> {
+    let function = call.encode();
+    let mut payload = Compact(nonce).encode();
+    payload.extend_from_slice(&function);
+    payload.push(IMMORTAL_ERA);
+    payload.extend_from_slice(genesis_hash.as_bytes());
+    Compact(ACCELERATION).encode_to(&mut payload);
+    let signature = if payload.len() > MAX_PAYLOAD_LEN {
+        signer.sign(&blake2_256(&payload))
+    } else {
+        signer.sign(&payload)
+    };
+
+    let mut extrinsic = vec![SIGNED_VERSION];
+    encode_address(signer.public().as_slice(), &mut extrinsic);
+    extrinsic.extend_from_slice(&signature.0);
+    Compact(nonce).encode_to(&mut extrinsic);
+    extrinsic.push(IMMORTAL_ERA);
+    Compact(ACCELERATION).encode_to(&mut extrinsic);
+    extrinsic.extend_from_slice(&function);
+    extrinsic.encode()
+}
+
+/// The signer and the nonce of the extrinsic encoded by `sign_extrinsic`, used by the mock node.
+#[cfg(test)]
+pub fn extrinsic_signer(extrinsic: &[u8]) -> Option<(AccountId, Index)> {
+    use crate::decode::DecodeWrapper;
+
+    let body = DecodeWrapper::new(extrinsic.to_vec()).bytes()?;
+    // version, signer address and signature precede the nonce
+    if body.len() < 98 || body[0] != SIGNED_VERSION || body[1] != ACCOUNT_ID_PREFIX {
+        return None;
+    }
+    let nonce = DecodeWrapper::new(body[98..].to_vec()).compact_index()?;
+    Some((AccountId::from_slice(&body[2..34]), nonce.0))
+}
+
+/// The hash of the encoded extrinsic, the same as the one returned by `author_submitExtrinsic`.
+pub fn extrinsic_hash(extrinsic: &[u8]) -> H256 {
+    H256::from(blake2_256(extrinsic))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_extrinsic() {
+        let signer = Pair::from_seed(&[0x42; 32]);
+        let call = TransferCall {
+            call_index: [8, 3],
+            dest: AccountId::from_slice(&[0x11; 32]),
+            token: SDOT_TOKEN.to_vec(),
+            value: 1000,
+            memo: b"memo".to_vec(),
+        };
+        let function = call.encode();
+        let mut expected = vec![8, 3, 0xff];
+        expected.extend_from_slice(&[0x11; 32]);
+        expected.extend_from_slice(b"\x10SDOT");
+        // compact 1000
+        expected.extend_from_slice(&[0xa1, 0x0f]);
+        expected.extend_from_slice(b"\x10memo");
+        assert_eq!(function, expected);
+
+        let extrinsic = sign_extrinsic(&call, &signer, 5, &H256::repeat_byte(1));
+        // the length prefix, version, signer, signature, nonce, era and acceleration precede the call
+        let (prefix, body) = extrinsic.split_at(2);
+        assert_eq!(Compact(body.len() as u32).encode(), prefix);
+        assert_eq!(body[0], SIGNED_VERSION);
+        assert_eq!(body[1], ACCOUNT_ID_PREFIX);
+        assert_eq!(&body[2..34], signer.public().as_slice());
+        assert_eq!(&body[98..101], &[5 << 2, IMMORTAL_ERA, (ACCELERATION << 2) as u8]);
+        assert_eq!(&body[101..], &function[..]);
+        assert_ne!(extrinsic, sign_extrinsic(&call, &signer, 6, &H256::repeat_byte(1)));
+        let account = AccountId::from_slice(signer.public().as_slice());
+        assert_eq!(extrinsic_signer(&extrinsic), Some((account, 5)));
+        assert_eq!(extrinsic_signer(&function), None);
+    }
+}
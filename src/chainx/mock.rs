@@ -0,0 +1,118 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+
+use parity_codec::Encode;
+
+use crate::error::{Error, Result};
+use crate::types::{Bytes, H256};
+
+use super::{account_nonce, extrinsic_hash, extrinsic_signer, nonce_key, ChainXNode};
+
+/// The in-memory ChainX node for tests, each submitted extrinsic is included in a new block,
+/// and the nonce of its signer is increased.
+///
+/// A stalled node keeps the extrinsics in the pool, they are included when the node resumes.
+#[derive(Debug)]
+pub struct MockNode {
+    /// The extrinsics of the blocks, starting from the genesis.
+    blocks: RefCell<Vec<Vec<Bytes>>>,
+    pool: RefCell<Vec<Bytes>>,
+    stalled: Cell<bool>,
+    storage: RefCell<HashMap<Vec<u8>, Vec<u8>>>,
+}
+
+impl Default for MockNode {
+    fn default() -> Self {
+        Self {
+            blocks: RefCell::new(vec![vec![]]),
+            pool: RefCell::new(vec![]),
+            stalled: Cell::new(false),
+            storage: RefCell::new(HashMap::new()),
+        }
+    }
+}
+
+impl MockNode {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_stalled(&self, stalled: bool) {
+        self.stalled.set(stalled);
+        if !stalled {
+            let pool = self.pool.replace(vec![]);
+            for extrinsic in pool {
+                self.include(extrinsic);
+            }
+        }
+    }
+
+    /// Drop the extrinsics of the pool, e.g. the node restarts.
+    pub fn clear_pool(&self) {
+        self.pool.borrow_mut().clear();
+    }
+
+    fn include(&self, extrinsic: Bytes) {
+        if let Some((signer, nonce)) = extrinsic_signer(&extrinsic.0) {
+            self.set_storage(nonce_key(&signer), (nonce + 1).encode());
+        }
+        self.blocks.borrow_mut().push(vec![extrinsic]);
+    }
+
+    pub fn set_storage(&self, key: Vec<u8>, value: Vec<u8>) {
+        self.storage.borrow_mut().insert(key, value);
+    }
+
+    /// All the extrinsics included in the blocks.
+    pub fn extrinsics(&self) -> Vec<Bytes> {
+        self.blocks.borrow().iter().flatten().cloned().collect()
+    }
+
+    fn hash_of(number: u64) -> H256 {
+        H256::from_low_u64_be(number + 1)
+    }
+}
+
+impl ChainXNode for MockNode {
+    fn block_hash(&self, number: u64) -> Result<Option<H256>> {
+        if number < self.blocks.borrow().len() as u64 {
+            Ok(Some(Self::hash_of(number)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn best_number(&self) -> Result<u64> {
+        Ok(self.blocks.borrow().len() as u64 - 1)
+    }
+
+    fn block_extrinsics(&self, hash: H256) -> Result<Vec<Bytes>> {
+        let number = hash.to_low_u64_be().wrapping_sub(1);
+        match self.blocks.borrow().get(number as usize) {
+            Some(extrinsics) if Self::hash_of(number) == hash => Ok(extrinsics.clone()),
+            _ => Err(Error::ChainXRpc(format!("non-existent block {:?}", hash))),
+        }
+    }
+
+    fn submit_extrinsic(&self, extrinsic: &[u8]) -> Result<H256> {
+        let hash = extrinsic_hash(extrinsic);
+        if let Some((signer, nonce)) = extrinsic_signer(extrinsic) {
+            if nonce < account_nonce(self, &signer)? {
+                return Err(Error::ChainXRpc(format!("stale nonce {} of extrinsic {:?}", nonce, hash)));
+            }
+        }
+        let extrinsic = Bytes(extrinsic.to_vec());
+        if self.stalled.get() {
+            if !self.pool.borrow().contains(&extrinsic) {
+                self.pool.borrow_mut().push(extrinsic);
+            }
+        } else {
+            self.include(extrinsic);
+        }
+        Ok(hash)
+    }
+
+    fn storage(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        Ok(self.storage.borrow().get(key).cloned())
+    }
+}
@@ -0,0 +1,34 @@
+mod extrinsic;
+#[cfg(test)]
+mod mock;
+mod node;
+mod state;
+mod submit;
+
+#[cfg(test)]
+pub use self::extrinsic::extrinsic_signer;
+pub use self::extrinsic::{extrinsic_hash, sign_extrinsic, TransferCall, SDOT_TOKEN};
+#[cfg(test)]
+pub use self::mock::MockNode;
+pub use self::node::HttpNode;
+#[cfg(test)]
+pub use self::state::{balance_key, nonce_key};
+pub use self::state::{account_nonce, sdot_balance};
+pub use self::submit::Submitter;
+
+use crate::error::Result;
+use crate::types::{Bytes, H256};
+
+/// The ChainX node which the SDOT transfers are submitted to.
+pub trait ChainXNode {
+    /// Get the hash of the block by number, `None` if the block does not exist (`chain_getBlockHash`).
+    fn block_hash(&self, number: u64) -> Result<Option<H256>>;
+    /// Get the number of the best block (`chain_getHeader`).
+    fn best_number(&self) -> Result<u64>;
+    /// Get the encoded extrinsics of the block (`chain_getBlock`).
+    fn block_extrinsics(&self, hash: H256) -> Result<Vec<Bytes>>;
+    /// Submit the encoded extrinsic to the transaction pool, return the extrinsic hash (`author_submitExtrinsic`).
+    fn submit_extrinsic(&self, extrinsic: &[u8]) -> Result<H256>;
+    /// Get the storage value of the best block, `None` if the key is not set (`state_getStorage`).
+    fn storage(&self, key: &[u8]) -> Result<Option<Vec<u8>>>;
+}
@@ -0,0 +1,85 @@
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use web3::futures::Future;
+use web3::transports::{EventLoopHandle, Http};
+use web3::Transport;
+
+use crate::error::{Error, Result};
+use crate::types::{Bytes, H256, U64};
+
+use super::ChainXNode;
+
+#[derive(Deserialize, Debug)]
+struct RpcHeader {
+    number: U64,
+}
+
+#[derive(Deserialize, Debug)]
+struct RpcBlock {
+    extrinsics: Vec<Bytes>,
+}
+
+#[derive(Deserialize, Debug)]
+struct RpcSignedBlock {
+    block: RpcBlock,
+}
+
+/// Submit the extrinsics to a ChainX node through the JSON-RPC interface.
+pub struct HttpNode {
+    // The event loop must be kept alive as long as the transport is used.
+    _eloop: EventLoopHandle,
+    transport: Http,
+}
+
+impl HttpNode {
+    pub fn new(url: &str) -> Result<Self> {
+        let (eloop, transport) = Http::new(url)?;
+        Ok(Self {
+            _eloop: eloop,
+            transport,
+        })
+    }
+
+    fn call<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Vec<serde_json::Value>,
+    ) -> Result<Option<T>> {
+        let value = self.transport.execute(method, params).wait()?;
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+impl ChainXNode for HttpNode {
+    fn block_hash(&self, number: u64) -> Result<Option<H256>> {
+        self.call("chain_getBlockHash", vec![number.into()])
+    }
+
+    fn best_number(&self) -> Result<u64> {
+        match self.call::<RpcHeader>("chain_getHeader", vec![])? {
+            Some(header) => Ok(header.number.as_u64()),
+            None => Err(Error::ChainXRpc("no best header".to_string())),
+        }
+    }
+
+    fn block_extrinsics(&self, hash: H256) -> Result<Vec<Bytes>> {
+        let params = vec![serde_json::to_value(hash)?];
+        match self.call::<RpcSignedBlock>("chain_getBlock", params)? {
+            Some(block) => Ok(block.block.extrinsics),
+            None => Err(Error::ChainXRpc(format!("non-existent block {:?}", hash))),
+        }
+    }
+
+    fn submit_extrinsic(&self, extrinsic: &[u8]) -> Result<H256> {
+        let params = vec![serde_json::to_value(Bytes(extrinsic.to_vec()))?];
+        match self.call("author_submitExtrinsic", params)? {
+            Some(hash) => Ok(hash),
+            None => Err(Error::ChainXRpc("no extrinsic hash".to_string())),
+        }
+    }
+
+    fn storage(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let params = vec![serde_json::to_value(Bytes(key.to_vec()))?];
+        Ok(self.call::<Bytes>("state_getStorage", params)?.map(|data| data.0))
+    }
+}
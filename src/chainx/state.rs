@@ -0,0 +1,97 @@
+use parity_codec::Encode;
+use substrate_primitives::hashing::blake2_256;
+
+use chainx_primitives::{AccountId, Balance, Index};
+
+use crate::decode::DecodeWrapper;
+use crate::error::{Error, Result};
+
+use super::{ChainXNode, SDOT_TOKEN};
+
+/// The storage prefix of `System::AccountNonce`.
+const ACCOUNT_NONCE: &[u8] = b"System AccountNonce";
+/// The storage prefix of `XAssets::AssetBalance`.
+const ASSET_BALANCE: &[u8] = b"XAssets AssetBalance";
+
+/// The key of the map storage: the blake2 hash of the prefix and the encoded map key.
+fn map_key(prefix: &[u8], key: &[u8]) -> Vec<u8> {
+    let mut encoded = prefix.to_vec();
+    encoded.extend_from_slice(key);
+    blake2_256(&encoded).to_vec()
+}
+
+/// The storage key of the nonce of the account.
+pub fn nonce_key(account: &AccountId) -> Vec<u8> {
+    map_key(ACCOUNT_NONCE, account.as_ref())
+}
+
+/// The storage key of the balance of the token of the account, keyed by `(account, token)`.
+pub fn balance_key(account: &AccountId, token: &[u8]) -> Vec<u8> {
+    let mut key = account.as_ref().to_vec();
+    token.to_vec().encode_to(&mut key);
+    map_key(ASSET_BALANCE, &key)
+}
+
+/// Read the nonce of the next extrinsic of the account, 0 if the account has not sent any.
+pub fn account_nonce<N: ChainXNode>(node: &N, account: &AccountId) -> Result<Index> {
+    match node.storage(&nonce_key(account))? {
+        Some(data) => match DecodeWrapper::new(data).nonce() {
+            Some(nonce) => Ok(nonce),
+            None => Err(Error::ChainXRpc(format!("invalid nonce of {:?}", account))),
+        },
+        None => Ok(0),
+    }
+}
+
+/// Read the free SDOT balance of the account, 0 if the account has no SDOT.
+pub fn sdot_balance<N: ChainXNode>(node: &N, account: &AccountId) -> Result<Balance> {
+    match node.storage(&balance_key(account, SDOT_TOKEN))? {
+        Some(data) => match DecodeWrapper::new(data).free_balance() {
+            Some(balance) => Ok(balance),
+            None => Err(Error::ChainXRpc(format!("invalid SDOT balance of {:?}", account))),
+        },
+        None => Ok(0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chainx::MockNode;
+
+    #[test]
+    fn test_storage_keys() {
+        let account = AccountId::from_slice(&[0x11; 32]);
+        let mut encoded = b"XAssets AssetBalance".to_vec();
+        encoded.extend_from_slice(&[0x11; 32]);
+        encoded.extend_from_slice(b"\x10SDOT");
+        assert_eq!(balance_key(&account, SDOT_TOKEN), blake2_256(&encoded).to_vec());
+        assert_ne!(nonce_key(&account), nonce_key(&AccountId::from_slice(&[0x12; 32])));
+    }
+
+    #[test]
+    fn test_read_state() {
+        let node = MockNode::new();
+        let account = AccountId::from_slice(&[0x11; 32]);
+        assert_eq!(account_nonce(&node, &account).unwrap(), 0);
+        assert_eq!(sdot_balance(&node, &account).unwrap(), 0);
+
+        node.set_storage(nonce_key(&account), 7u64.encode());
+        assert_eq!(account_nonce(&node, &account).unwrap(), 7);
+        node.set_storage(nonce_key(&account), vec![1, 2]);
+        assert!(account_nonce(&node, &account).is_err());
+
+        // `CodecBTreeMap<AssetType, Balance>` with the free (0) and the staking reserved (1) balances
+        let balances = vec![(0u8, 1_000u64), (1u8, 50u64)];
+        let encoded = balances.encode();
+        assert_eq!(&encoded[..10], &[8, 0, 0xe8, 3, 0, 0, 0, 0, 0, 0][..]);
+        node.set_storage(balance_key(&account, SDOT_TOKEN), encoded);
+        assert_eq!(sdot_balance(&node, &account).unwrap(), 1_000);
+        // only reserved
+        node.set_storage(balance_key(&account, SDOT_TOKEN), vec![(1u8, 50u64)].encode());
+        assert_eq!(sdot_balance(&node, &account).unwrap(), 0);
+        // a bare balance is not the map
+        node.set_storage(balance_key(&account, SDOT_TOKEN), 1_000u64.encode());
+        assert!(sdot_balance(&node, &account).is_err());
+    }
+}
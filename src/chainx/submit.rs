@@ -0,0 +1,447 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use substrate_primitives::ed25519::Pair;
+
+use chainx_primitives::{AccountId, Balance, Index};
+
+use crate::confirm::check_record;
+use crate::error::{Error, Result};
+use crate::eth::check_tx_data;
+use crate::ledger::{Ledger, MappingKind, MappingStatus};
+use crate::report::{Entry, Outcome, Report};
+use crate::source::TxSource;
+use crate::types::{H160, H256};
+
+use super::{account_nonce, extrinsic_hash, sdot_balance, sign_extrinsic, ChainXNode, TransferCall, SDOT_TOKEN};
+
+/// Sign the SDOT transfers by the payout account, submit them to the node and wait for the inclusion.
+///
+/// The nonce of the payout account is read from the node once, and then tracked locally.
+pub struct Submitter<N> {
+    node: N,
+    signer: Pair,
+    genesis_hash: H256,
+    call_index: [u8; 2],
+    nonce: Index,
+    timeout: Duration,
+    interval: Duration,
+}
+
+impl<N: ChainXNode> Submitter<N> {
+    /// `call_index` is the module index and the call index of `XAssets::transfer` in the runtime of the node.
+    pub fn new(node: N, signer: Pair, call_index: [u8; 2]) -> Result<Self> {
+        let genesis_hash = match node.block_hash(0)? {
+            Some(hash) => hash,
+            None => return Err(Error::ChainXRpc("no genesis block".to_string())),
+        };
+        let nonce = account_nonce(&node, &AccountId::from_slice(signer.public().as_slice()))?;
+        Ok(Self {
+            node,
+            signer,
+            genesis_hash,
+            call_index,
+            nonce,
+            timeout: Duration::from_secs(60),
+            interval: Duration::from_secs(2),
+        })
+    }
+
+    /// Override the nonce read from the node, e.g. when the pool has pending extrinsics of the payout account.
+    pub fn with_nonce(mut self, nonce: Index) -> Self {
+        self.nonce = nonce;
+        self
+    }
+
+    /// Wait at most `timeout` for the inclusion, polling the new blocks every `interval`.
+    pub fn with_timeout(mut self, timeout: Duration, interval: Duration) -> Self {
+        self.timeout = timeout;
+        self.interval = interval;
+        self
+    }
+
+    #[cfg(test)]
+    pub fn node(&self) -> &N {
+        &self.node
+    }
+
+    /// The nonce of the next extrinsic.
+    #[allow(dead_code)]
+    pub fn nonce(&self) -> Index {
+        self.nonce
+    }
+
+    fn transfer_call(&self, dest: AccountId, value: Balance, memo: Vec<u8>) -> TransferCall {
+        TransferCall {
+            call_index: self.call_index,
+            dest,
+            token: SDOT_TOKEN.to_vec(),
+            value,
+            memo,
+        }
+    }
+
+    /// Transfer the SDOT to `dest` and wait for the inclusion, return the extrinsic hash.
+    #[cfg(test)]
+    pub fn transfer(&mut self, dest: AccountId, value: Balance, memo: Vec<u8>) -> Result<H256> {
+        let call = self.transfer_call(dest, value, memo);
+        let extrinsic = sign_extrinsic(&call, &self.signer, self.nonce, &self.genesis_hash);
+        self.submit(&extrinsic)
+    }
+
+    fn account(&self) -> AccountId {
+        AccountId::from_slice(self.signer.public().as_slice())
+    }
+
+    fn submit(&mut self, extrinsic: &[u8]) -> Result<H256> {
+        let start = self.node.best_number()?;
+        let hash = self.node.submit_extrinsic(extrinsic)?;
+        // The nonce is taken once the extrinsic is in the pool, even if it is never included.
+        self.nonce += 1;
+        let number = self.wait_for_inclusion(extrinsic, start)?;
+        info!("SDOT transfer {:?} included in ChainX block #{}", hash, number);
+        Ok(hash)
+    }
+
+    /// Scan the blocks after `start` until the extrinsic is found, return the block number.
+    fn wait_for_inclusion(&self, extrinsic: &[u8], start: u64) -> Result<u64> {
+        let deadline = Instant::now() + self.timeout;
+        let mut next = start + 1;
+        loop {
+            let best = self.node.best_number()?;
+            while next <= best {
+                if let Some(hash) = self.node.block_hash(next)? {
+                    if self.node.block_extrinsics(hash)?.iter().any(|included| included.0 == extrinsic) {
+                        return Ok(next);
+                    }
+                }
+                next += 1;
+            }
+            if Instant::now() >= deadline {
+                warn!("ChainX extrinsic is not included after {:?}", self.timeout);
+                return Err(Error::MappingTimeout);
+            }
+            thread::sleep(self.interval);
+        }
+    }
+
+    /// Pay the SDOT of the mapping record, and mark it as paid after the inclusion.
+    ///
+    /// The inclusion proof and the Ethereum block of a mapping transaction are checked again before the payout,
+    /// the signed messages and claims have no block.
+    ///
+    /// The submitted transfer is saved to the ledger before the submission. If the inclusion is not seen,
+    /// e.g. timeout or crash, the retry checks the nonce of the payout account instead of paying again:
+    /// the transfer has been included if the nonce has been used, otherwise the same extrinsic is submitted again.
+    /// The payout account must not be used by anything else.
+    /// A new transfer is rejected if the free SDOT of the payout account cannot cover it.
+    pub fn pay<S: TxSource>(&mut self, source: &S, ledger: &mut Ledger, from: &H160) -> Result<H256> {
+        let record = match ledger.get(from) {
+            Some(record) if record.status != MappingStatus::Paid => record.clone(),
+            _ => {
+                warn!("No unpaid mapping of {:?}", from);
+                return Err(Error::NoSdot);
+            }
+        };
+        let who = check_tx_data(record.who.as_bytes())?;
+        let call = self.transfer_call(who, record.amount, format!("{:#x}", record.tx_hash).into_bytes());
+        if let MappingStatus::Submitted { nonce, extrinsic_hash } = record.status {
+            return self.resume(ledger, from, &call, nonce, extrinsic_hash);
+        }
+
+        if record.kind == MappingKind::Tx {
+            record.verify_proof()?;
+            check_record(source, &record)?;
+        }
+        let balance = sdot_balance(&self.node, &self.account())?;
+        if balance < record.amount {
+            warn!("Payout account has {} SDOT, cannot pay {} to {:?}", balance, record.amount, from);
+            return Err(Error::InvalidPayout(format!("insufficient SDOT of the payout account: {}", balance)));
+        }
+        // Never reuse the nonce of a submitted transfer, which may still be in the pool.
+        self.nonce = self.nonce.max(ledger.next_payout_nonce());
+        let nonce = self.nonce;
+        let extrinsic = sign_extrinsic(&call, &self.signer, nonce, &self.genesis_hash);
+        let hash = extrinsic_hash(&extrinsic);
+        ledger.set_status(from, MappingStatus::Submitted { nonce, extrinsic_hash: hash })?;
+        self.submit(&extrinsic)?;
+        ledger.set_status(from, MappingStatus::Paid)?;
+        Ok(hash)
+    }
+
+    /// Pay the mapping records of the addresses, the failure of a payout never stops the rest.
+    pub fn pay_all<S: TxSource>(&mut self, source: &S, ledger: &mut Ledger, addresses: &[H160]) -> Report {
+        let mut report = Report::default();
+        for from in addresses {
+            let record = ledger.get(from).cloned();
+            let hash = record.as_ref().map(|record| format!("{:?}", record.tx_hash)).unwrap_or_default();
+            let entry = match self.pay(source, ledger, from) {
+                Ok(extrinsic_hash) => Entry {
+                    hash,
+                    outcome: Outcome::Ok,
+                    who: record.as_ref().map(|record| record.who.clone()),
+                    from: Some(*from),
+                    amount: record.as_ref().map(|record| record.amount),
+                    message: Some(format!("extrinsic {:?}", extrinsic_hash)),
+                },
+                Err(err) => {
+                    error!("Pay the SDOT of {:?} failed: {}", from, err);
+                    Entry {
+                        from: Some(*from),
+                        ..Entry::failed(hash, Outcome::from_error(&err), err.to_string())
+                    }
+                }
+            };
+            report.push(entry);
+        }
+        report
+    }
+
+    /// Finish the payout submitted with the `nonce` by a previous `pay`.
+    fn resume(&mut self, ledger: &mut Ledger, from: &H160, call: &TransferCall, nonce: Index, hash: H256) -> Result<H256> {
+        if account_nonce(&self.node, &self.account())? > nonce {
+            info!("SDOT transfer {:?} of {:?} with nonce {} has been included", hash, from, nonce);
+            ledger.set_status(from, MappingStatus::Paid)?;
+            return Ok(hash);
+        }
+        // The same signed extrinsic, which can only be included once with the nonce.
+        let extrinsic = sign_extrinsic(call, &self.signer, nonce, &self.genesis_hash);
+        if extrinsic_hash(&extrinsic) != hash {
+            warn!("SDOT transfer {:?} of {:?} was not signed by this payout account", hash, from);
+            return Err(Error::ChainXRpc(format!("cannot resubmit the SDOT transfer {:?}", hash)));
+        }
+        let start = self.node.best_number()?;
+        if let Err(err) = self.node.submit_extrinsic(&extrinsic) {
+            // e.g. the extrinsic is still in the pool
+            warn!("Resubmit SDOT transfer {:?} failed: {}", hash, err);
+        }
+        let number = self.wait_for_inclusion(&extrinsic, start)?;
+        info!("SDOT transfer {:?} included in ChainX block #{}", hash, number);
+        ledger.set_status(from, MappingStatus::Paid)?;
+        Ok(hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chainx::{balance_key, extrinsic_signer, nonce_key, MockNode};
+    use parity_codec::Encode;
+    use crate::ledger::MappingRecord;
+    use crate::mapping::MappingChecker;
+    use crate::payout::{PayoutCalculator, Ratio};
+    use crate::snapshot::{OwnerSnapshot, SnapshotFormat};
+    use crate::source::FixtureSource;
+    use crate::verify::ReceiptCache;
+    use std::fs;
+
+    fn submitter() -> Submitter<MockNode> {
+        let signer = Pair::from_seed(&[0x42; 32]);
+        let node = MockNode::new();
+        let account = AccountId::from_slice(signer.public().as_slice());
+        node.set_storage(balance_key(&account, SDOT_TOKEN), vec![(0u8, 1_000_000u64)].encode());
+        Submitter::new(node, signer, [8, 3])
+            .unwrap()
+            .with_nonce(5)
+            .with_timeout(Duration::from_millis(50), Duration::from_millis(10))
+    }
+
+    #[test]
+    fn test_transfer() {
+        let dest = check_tx_data(b"5UdrXD14mzNMnosk5PAYVTbWjFKrMwhjWuicLRGU3M8JcYBg").unwrap();
+        let mut submitter = submitter();
+        let hash = submitter.transfer(dest, 1000, b"memo".to_vec()).unwrap();
+        let extrinsics = submitter.node().extrinsics();
+        assert_eq!(extrinsics.len(), 1);
+        assert_eq!(extrinsic_hash(&extrinsics[0].0), hash);
+        assert_eq!(submitter.nonce(), 6);
+
+        submitter.node().set_stalled(true);
+        match submitter.transfer(dest, 1000, b"memo".to_vec()) {
+            Err(Error::MappingTimeout) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert_eq!(submitter.nonce(), 7);
+    }
+
+    #[test]
+    fn test_read_nonce() {
+        let signer = Pair::from_seed(&[0x42; 32]);
+        let node = MockNode::new();
+        node.set_storage(nonce_key(&AccountId::from_slice(signer.public().as_slice())), 9u64.encode());
+        let submitter = Submitter::new(node, signer, [8, 3]).unwrap();
+        assert_eq!(submitter.nonce(), 9);
+    }
+
+    #[test]
+    fn test_pay_requires_proof() {
+        let path = std::env::temp_dir().join(format!("sdot-ledger-{}-pay-requires-proof.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let record = MappingRecord {
+            from: H160::repeat_byte(1),
+            tx_hash: H256::repeat_byte(2),
+            kind: MappingKind::Tx,
+            who: "5UdrXD14mzNMnosk5PAYVTbWjFKrMwhjWuicLRGU3M8JcYBg".to_string(),
+            amount: 100,
+            block_number: 10,
+            block_hash: Some(H256::repeat_byte(5)),
+            proof: None,
+            receipt_proof: None,
+            status: MappingStatus::Pending,
+        };
+        let mut ledger = Ledger::open(path.clone()).unwrap();
+        ledger.record(record.clone()).unwrap();
+        let source = FixtureSource::new(std::env::temp_dir());
+
+        let mut submitter = submitter();
+        match submitter.pay(&source, &mut ledger, &record.from) {
+            Err(Error::InvalidEthProof) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match submitter.pay(&source, &mut ledger, &H160::repeat_byte(3)) {
+            Err(Error::NoSdot) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(submitter.node().extrinsics().is_empty());
+        assert_eq!(ledger.get(&record.from).unwrap().status, MappingStatus::Pending);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_pay_insufficient_balance() {
+        let path = std::env::temp_dir().join(format!("sdot-ledger-{}-pay-insufficient.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let record = MappingRecord {
+            from: H160::repeat_byte(1),
+            tx_hash: H256::repeat_byte(2),
+            kind: MappingKind::SignedMessage,
+            who: "5UdrXD14mzNMnosk5PAYVTbWjFKrMwhjWuicLRGU3M8JcYBg".to_string(),
+            amount: 100,
+            block_number: 0,
+            block_hash: None,
+            proof: None,
+            receipt_proof: None,
+            status: MappingStatus::Pending,
+        };
+        let mut ledger = Ledger::open(path.clone()).unwrap();
+        ledger.record(record.clone()).unwrap();
+        let source = FixtureSource::new(std::env::temp_dir());
+
+        let mut submitter = submitter();
+        submitter.node().set_storage(balance_key(&submitter.account(), SDOT_TOKEN), vec![(0u8, 99u64)].encode());
+        match submitter.pay(&source, &mut ledger, &record.from) {
+            Err(Error::InvalidPayout(_)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(submitter.node().extrinsics().is_empty());
+        assert_eq!(ledger.get(&record.from).unwrap().status, MappingStatus::Pending);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_pay_once() {
+        let path = std::env::temp_dir().join(format!("sdot-ledger-{}-pay-once.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let message = |from: H160| MappingRecord {
+            from,
+            tx_hash: H256::from(from),
+            kind: MappingKind::SignedMessage,
+            who: "5UdrXD14mzNMnosk5PAYVTbWjFKrMwhjWuicLRGU3M8JcYBg".to_string(),
+            amount: 100,
+            block_number: 0,
+            block_hash: None,
+            proof: None,
+            receipt_proof: None,
+            status: MappingStatus::Pending,
+        };
+        let (alice, bob) = (H160::repeat_byte(1), H160::repeat_byte(2));
+        let mut ledger = Ledger::open(path.clone()).unwrap();
+        ledger.record(message(alice)).unwrap();
+        ledger.record(message(bob)).unwrap();
+        let source = FixtureSource::new(std::env::temp_dir());
+        let mut submitter = submitter();
+
+        // the transfer is saved before the submission, and stays submitted after the timeout
+        submitter.node().set_stalled(true);
+        match submitter.pay(&source, &mut ledger, &alice) {
+            Err(Error::MappingTimeout) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        let hash = match Ledger::open(path.clone()).unwrap().get(&alice).unwrap().status {
+            MappingStatus::Submitted { nonce: 5, extrinsic_hash } => extrinsic_hash,
+            other => panic!("unexpected status: {:?}", other),
+        };
+        // the retry submits the same extrinsic again
+        assert!(submitter.pay(&source, &mut ledger, &alice).is_err());
+        assert_eq!(ledger.get(&alice).unwrap().status, MappingStatus::Submitted { nonce: 5, extrinsic_hash: hash });
+
+        // included after the timeout, the retry of a new submitter finds the nonce used
+        submitter.node().set_stalled(false);
+        let node = std::mem::replace(&mut submitter.node, MockNode::new());
+        let mut submitter = Submitter::new(node, Pair::from_seed(&[0x42; 32]), [8, 3])
+            .unwrap()
+            .with_timeout(Duration::from_millis(50), Duration::from_millis(10));
+        assert_eq!(submitter.nonce(), 6);
+        assert_eq!(submitter.pay(&source, &mut ledger, &alice).unwrap(), hash);
+        assert_eq!(ledger.get(&alice).unwrap().status, MappingStatus::Paid);
+        assert_eq!(submitter.node().extrinsics().len(), 1);
+        assert!(submitter.pay(&source, &mut ledger, &alice).is_err());
+
+        // dropped by the pool, the retry submits it again with the same nonce
+        submitter.node().set_stalled(true);
+        assert!(submitter.pay(&source, &mut ledger, &bob).is_err());
+        submitter.node().clear_pool();
+        submitter.node().set_stalled(false);
+        let hash = submitter.pay(&source, &mut ledger, &bob).unwrap();
+        let extrinsics = submitter.node().extrinsics();
+        assert_eq!(extrinsics.len(), 2);
+        assert_eq!(extrinsic_hash(&extrinsics[1].0), hash);
+        assert_eq!(extrinsic_signer(&extrinsics[1].0).unwrap().1, 6);
+        assert_eq!(Ledger::open(path.clone()).unwrap().get(&bob).unwrap().status, MappingStatus::Paid);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_pay_all() {
+        let path = std::env::temp_dir().join(format!("sdot-ledger-{}-pay-all.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let source = FixtureSource::new(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures"));
+        let (owners, _) =
+            OwnerSnapshot::parse("9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f,1500\n", SnapshotFormat::Csv).unwrap();
+        let calculator = PayoutCalculator::new(Ratio::new(1, 1), 3, 3);
+        let checker = MappingChecker {
+            source: &source,
+            owners: &owners,
+            calculator: &calculator,
+            min_confirmations: 1,
+            receipts: &ReceiptCache::default(),
+        };
+        let hash = "ece0a27a08d6a1765d2331e596a42da43bbb1acb2293fc818d0259a56b0a0166".parse().unwrap();
+        let mut ledger = Ledger::open(path.clone()).unwrap();
+        let mapping = checker.check_hash(hash, &ledger).unwrap();
+        let from = mapping.from;
+        checker.accept(&mut ledger, &mapping).unwrap();
+        assert_eq!(ledger.unpaid(), vec![from]);
+
+        let mut submitter = submitter();
+        let unpaid = ledger.unpaid();
+        let report = submitter.pay_all(&source, &mut ledger, &unpaid);
+        assert!(!report.has_failures());
+        assert_eq!(report.entries[0].amount, Some(1500));
+        let extrinsics = submitter.node().extrinsics();
+        assert_eq!(extrinsics.len(), 1);
+        assert_eq!(extrinsic_signer(&extrinsics[0].0).unwrap().1, 5);
+        assert_eq!(Ledger::open(path.clone()).unwrap().get(&from).unwrap().status, MappingStatus::Paid);
+        assert!(ledger.unpaid().is_empty());
+
+        // paying again is reported as a duplicate and submits nothing
+        let report = submitter.pay_all(&source, &mut ledger, &[from]);
+        assert_eq!(report.entries[0].outcome, Outcome::Duplicate);
+        assert_eq!(submitter.node().extrinsics().len(), 1);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,17 +1,292 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+
 use structopt::StructOpt;
+use substrate_primitives::ed25519::Pair;
+
+use crate::error::Result;
+use crate::payout::{PayoutCalculator, Ratio, Rounding};
+use crate::report::ReportFormat;
+use crate::source::{EtherScanApi, FixtureSource, NodeSource, TxSource};
+use crate::types::H160;
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "sdot-service", author = "ChainX", about = "For mapping sdot")]
-
 pub struct CliConfig {
+    /// Serve the JSON-RPC if no subcommand is given
+    #[structopt(subcommand)]
+    pub cmd: Option<Command>,
+}
+
+#[derive(Debug, StructOpt)]
+pub enum Command {
+    /// Serve the SDOT mapping check over JSON-RPC
+    #[structopt(name = "serve")]
+    Serve(ServeOpt),
+    /// Verify the mapping transactions of a hash file and write a report
+    #[structopt(name = "verify")]
+    Verify(VerifyOpt),
+    /// Scan the Ethereum blocks for the mapping transactions to the deposit address
+    #[structopt(name = "scan")]
+    Scan(ScanOpt),
+    /// Decode and check a raw signed Ethereum transaction
+    #[structopt(name = "decode-tx")]
+    DecodeTx(DecodeTxOpt),
+    /// Validate the DOT owner snapshot, reporting the duplicate and malformed entries
+    #[structopt(name = "snapshot")]
+    Snapshot(SnapshotOpt),
+    /// Send the SDOT of the unpaid mappings of the ledger to the ChainX accounts
+    #[structopt(name = "pay")]
+    Pay(PayOpt),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SourceKind {
+    EtherScan,
+    Node,
+    Fixture,
+}
+
+impl FromStr for SourceKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, String> {
+        match s {
+            "etherscan" => Ok(SourceKind::EtherScan),
+            "node" => Ok(SourceKind::Node),
+            "fixture" => Ok(SourceKind::Fixture),
+            _ => Err(format!("invalid source '{}', expected etherscan, node or fixture", s)),
+        }
+    }
+}
+
+/// Where the Ethereum data come from.
+#[derive(Clone, Debug, StructOpt)]
+pub struct SourceOpt {
+    /// Ethereum data source: etherscan, node or fixture
+    #[structopt(long = "source", value_name = "SOURCE", default_value = "etherscan")]
+    pub source: SourceKind,
+    /// EtherScan API key, used by the etherscan source
+    #[structopt(long = "etherscan-api-key", value_name = "KEY", env = "ETHERSCAN_API_KEY", hide_env_values = true)]
+    pub etherscan_api_key: Option<String>,
+    /// JSON-RPC URL of the Ethereum node, used by the node source
+    #[structopt(long = "eth-rpc", value_name = "URL", default_value = "http://127.0.0.1:8545")]
+    pub eth_rpc: String,
+    /// Directory of the recorded JSON-RPC responses, used by the fixture source
+    #[structopt(long = "fixtures", value_name = "DIR", default_value = "fixtures", parse(from_os_str))]
+    pub fixtures: PathBuf,
+}
+
+impl SourceOpt {
+    pub fn open(&self) -> Result<Box<dyn TxSource + Send + Sync>> {
+        Ok(match self.source {
+            SourceKind::EtherScan => Box::new(EtherScanApi::new(self.etherscan_api_key.clone())),
+            SourceKind::Node => Box::new(NodeSource::new(&self.eth_rpc)?),
+            SourceKind::Fixture => Box::new(FixtureSource::new(self.fixtures.clone())),
+        })
+    }
+}
+
+/// How the mapping transactions are checked.
+#[derive(Clone, Debug, StructOpt)]
+pub struct CheckOpt {
+    /// The ledger file recording the Ethereum addresses which have received the SDOT
+    #[structopt(long = "ledger", value_name = "FILE", default_value = "sdot-ledger.json", parse(from_os_str))]
+    pub ledger: PathBuf,
+    /// Minimum number of confirmations of the mapping transaction
+    #[structopt(long = "confirmations", value_name = "N", default_value = "12")]
+    pub confirmations: u64,
+}
+
+/// How the SDOT amount is computed from the DOT owner snapshot.
+#[derive(Clone, Debug, StructOpt)]
+pub struct PayoutOpt {
+    /// SDOT per DOT, as a fraction `3/2` or a decimal `1.5`
+    #[structopt(long = "sdot-ratio", value_name = "RATIO", default_value = "1")]
+    pub sdot_ratio: Ratio,
+    /// Decimals of the DOT balance in the owner snapshot
+    #[structopt(long = "dot-decimals", value_name = "N", default_value = "3")]
+    pub dot_decimals: u32,
+    /// Decimals of SDOT on ChainX
+    #[structopt(long = "sdot-decimals", value_name = "N", default_value = "3")]
+    pub sdot_decimals: u32,
+    /// Rounding of the SDOT amount: down, up or nearest
+    #[structopt(long = "rounding", value_name = "MODE", default_value = "down")]
+    pub rounding: Rounding,
+}
+
+impl PayoutOpt {
+    pub fn calculator(&self) -> PayoutCalculator {
+        PayoutCalculator::new(self.sdot_ratio, self.dot_decimals, self.sdot_decimals).with_rounding(self.rounding)
+    }
+}
+
+/// Where and how the report is written.
+#[derive(Clone, Debug, StructOpt)]
+pub struct OutputOpt {
+    /// Report format: json, csv or table
+    #[structopt(long = "format", value_name = "FORMAT", default_value = "table")]
+    pub format: ReportFormat,
+    /// Write the report to the file instead of stdout
+    #[structopt(short = "o", long = "output", value_name = "FILE", parse(from_os_str))]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug, StructOpt)]
+pub struct ServeOpt {
     #[structopt(long = "rpc-port", value_name = "PORT", default_value = "8100")]
     pub rpc_port: u16,
+    /// Chain ID of the EIP-712 claim domain
+    #[structopt(long = "claim-chain-id", value_name = "ID", default_value = "1")]
+    pub claim_chain_id: u64,
+    /// Verifying contract of the EIP-712 claim domain, the typed claims are rejected without it
+    #[structopt(long = "claim-contract", value_name = "ADDRESS", parse(try_from_str = parse_address))]
+    pub claim_contract: Option<H160>,
+    /// The DOT owner snapshot with balances, .csv or .json
+    #[structopt(long = "owners", value_name = "FILE", default_value = "eth-addr.txt", parse(from_os_str))]
+    pub owners: PathBuf,
+    #[structopt(flatten)]
+    pub check: CheckOpt,
+    #[structopt(flatten)]
+    pub payout: PayoutOpt,
+    #[structopt(flatten)]
+    pub source: SourceOpt,
+}
+
+#[derive(Clone, Debug, StructOpt)]
+pub struct VerifyOpt {
+    /// The Ethereum tx hashes to verify, one per line
+    #[structopt(long = "hashes", value_name = "FILE", default_value = "tx-hash.txt", parse(from_os_str))]
+    pub hashes: PathBuf,
+    /// The DOT owner snapshot with balances, .csv or .json
+    #[structopt(long = "owners", value_name = "FILE", default_value = "eth-addr.txt", parse(from_os_str))]
+    pub owners: PathBuf,
+    #[structopt(flatten)]
+    pub check: CheckOpt,
+    #[structopt(flatten)]
+    pub payout: PayoutOpt,
+    #[structopt(flatten)]
+    pub source: SourceOpt,
+    #[structopt(flatten)]
+    pub output: OutputOpt,
+}
+
+#[derive(Clone, Debug, StructOpt)]
+pub struct ScanOpt {
+    /// The deposit address receiving the mapping transactions
+    #[structopt(long = "deposit", value_name = "ADDRESS", parse(try_from_str = parse_address))]
+    pub deposit: H160,
+    /// Scan the transfers of the ERC-20 token to the deposit address instead of the plain transactions
+    #[structopt(long = "token", value_name = "ADDRESS", parse(try_from_str = parse_address))]
+    pub token: Option<H160>,
+    /// The first block to scan
+    #[structopt(long = "from", value_name = "NUMBER", default_value = "0")]
+    pub from: u64,
+    /// The last block to scan, the confirmed chain head by default
+    #[structopt(long = "to", value_name = "NUMBER")]
+    pub to: Option<u64>,
+    /// Keep following the chain head
+    #[structopt(long = "follow", conflicts_with = "to")]
+    pub follow: bool,
+    /// Seconds between the polls of the chain head when following
+    #[structopt(long = "interval", value_name = "SECONDS", default_value = "15")]
+    pub interval: u64,
+    /// The file saving the last scanned block
+    #[structopt(long = "checkpoint", value_name = "FILE", default_value = "scanner-checkpoint", parse(from_os_str))]
+    pub checkpoint: PathBuf,
+    /// The DOT owner snapshot with balances, .csv or .json
+    #[structopt(long = "owners", value_name = "FILE", default_value = "eth-addr.txt", parse(from_os_str))]
+    pub owners: PathBuf,
+    /// Append each mapping as a JSON line to the file instead of stdout
+    #[structopt(short = "o", long = "output", value_name = "FILE", parse(from_os_str))]
+    pub output: Option<PathBuf>,
+    #[structopt(flatten)]
+    pub check: CheckOpt,
+    #[structopt(flatten)]
+    pub payout: PayoutOpt,
+    #[structopt(flatten)]
+    pub source: SourceOpt,
+}
+
+#[derive(Clone, Debug, StructOpt)]
+pub struct DecodeTxOpt {
+    /// The raw signed Ethereum transaction in hex
+    #[structopt(value_name = "HEX")]
+    pub raw_tx: String,
+    #[structopt(flatten)]
+    pub output: OutputOpt,
+}
+
+#[derive(Clone, Debug, StructOpt)]
+pub struct SnapshotOpt {
+    /// The DOT owner snapshot, the format is guessed from the extension: .csv, .json or plain text
+    #[structopt(long = "owners", value_name = "FILE", default_value = "eth-addr.txt", parse(from_os_str))]
+    pub owners: PathBuf,
+    #[structopt(flatten)]
+    pub output: OutputOpt,
+}
+
+#[derive(Clone, Debug, StructOpt)]
+pub struct PayOpt {
+    /// The ledger file of the mappings
+    #[structopt(long = "ledger", value_name = "FILE", default_value = "sdot-ledger.json", parse(from_os_str))]
+    pub ledger: PathBuf,
+    /// Pay the mapping of the Ethereum address only, all the unpaid mappings by default
+    #[structopt(long = "from", value_name = "ADDRESS", parse(try_from_str = parse_address))]
+    pub from: Option<H160>,
+    /// JSON-RPC URL of the ChainX node
+    #[structopt(long = "chainx-rpc", value_name = "URL", default_value = "http://127.0.0.1:8086")]
+    pub chainx_rpc: String,
+    /// The ed25519 seed of the payout account in hex
+    #[structopt(long = "signer-seed", value_name = "HEX", env = "SDOT_SIGNER_SEED", hide_env_values = true)]
+    pub signer_seed: String,
+    /// The module index and the call index of `XAssets::transfer` in the ChainX runtime, e.g. `8,3`
+    #[structopt(long = "call-index", value_name = "MODULE,CALL", parse(try_from_str = parse_call_index))]
+    pub call_index: [u8; 2],
+    /// The nonce of the first transfer, read from the node by default
+    #[structopt(long = "nonce", value_name = "N")]
+    pub nonce: Option<u64>,
+    /// Seconds to wait for the inclusion of each transfer
+    #[structopt(long = "timeout", value_name = "SECONDS", default_value = "60")]
+    pub timeout: u64,
+    #[structopt(flatten)]
+    pub source: SourceOpt,
+    #[structopt(flatten)]
+    pub output: OutputOpt,
+}
+
+impl PayOpt {
+    pub fn signer(&self) -> Result<Pair> {
+        let seed = hex::decode(self.signer_seed.trim().trim_start_matches("0x"))?;
+        if seed.len() != 32 {
+            return Err(hex::FromHexError::InvalidStringLength.into());
+        }
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(&seed);
+        Ok(Pair::from_seed(&bytes))
+    }
 }
 
 pub fn init() -> CliConfig {
     CliConfig::from_args()
 }
 
-pub fn config_url(conf: &CliConfig) -> String {
-    format!("0.0.0.0:{}", conf.rpc_port)
+pub fn config_url(opt: &ServeOpt) -> String {
+    format!("0.0.0.0:{}", opt.rpc_port)
+}
+
+fn parse_call_index(s: &str) -> std::result::Result<[u8; 2], String> {
+    let indexes = s.split(',').map(|index| index.trim().parse::<u8>()).collect::<Vec<_>>();
+    match indexes.as_slice() {
+        [Ok(module), Ok(call)] => Ok([*module, *call]),
+        _ => Err(format!("invalid call index '{}', expected MODULE,CALL", s)),
+    }
+}
+
+fn parse_address(s: &str) -> std::result::Result<H160, hex::FromHexError> {
+    let bytes = hex::decode(s.trim_start_matches("0x"))?;
+    if bytes.len() != 20 {
+        return Err(hex::FromHexError::InvalidStringLength);
+    }
+    Ok(H160::from_slice(&bytes))
 }
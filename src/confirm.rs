@@ -0,0 +1,130 @@
+use crate::error::{Error, Result};
+use crate::ledger::MappingRecord;
+use crate::source::TxSource;
+use crate::types::{FullTransaction, H256};
+
+/// The number of confirmations of the block `number` when the chain head is `head`,
+/// the block itself counts as one confirmation.
+pub fn confirmations(head: u64, number: u64) -> u64 {
+    if head < number {
+        0
+    } else {
+        head - number + 1
+    }
+}
+
+/// Check the transaction has been mined with at least `min_confirmations` confirmations.
+///
+/// Return the number of confirmations.
+pub fn check_confirmations<S: TxSource>(source: &S, tx: &FullTransaction, min_confirmations: u64) -> Result<u64> {
+    let number = match tx.block_number {
+        Some(number) => number.as_u64(),
+        None => {
+            warn!("Ethereum tx {:?} is pending", tx.hash);
+            return Err(Error::PendingConfirmations(0, min_confirmations));
+        }
+    };
+    let head = source.get_block_number()?;
+    let confirmations = confirmations(head, number);
+    if confirmations < min_confirmations {
+        warn!(
+            "Ethereum tx {:?} in block #{} has {}/{} confirmations",
+            tx.hash, number, confirmations, min_confirmations
+        );
+        return Err(Error::PendingConfirmations(confirmations, min_confirmations));
+    }
+    Ok(confirmations)
+}
+
+/// Check the block `number` is still the block `hash` on the canonical chain.
+pub fn check_canonical<S: TxSource>(source: &S, number: u64, hash: H256) -> Result<()> {
+    let block = source.get_block_by_number(number)?;
+    if block.hash != Some(hash) {
+        warn!("Ethereum block #{} has been reorganized: {:?} => {:?}", number, hash, block.hash);
+        return Err(Error::EthChainReorg);
+    }
+    Ok(())
+}
+
+/// Re-verify the recorded block of the mapping transaction before the final mapping,
+/// the transaction may have been dropped by a reorg since it was accepted.
+pub fn check_record<S: TxSource>(source: &S, record: &MappingRecord) -> Result<()> {
+    match record.block_hash {
+        Some(hash) => check_canonical(source, record.block_number, hash),
+        None => {
+            // The record was accepted before the block hash is recorded, fetch the tx again.
+            let tx = source.get_tx_by_hash(record.tx_hash)?;
+            match tx.block_number {
+                Some(number) if number.as_u64() == record.block_number => Ok(()),
+                _ => {
+                    warn!("Ethereum tx {:?} is no longer in block #{}", record.tx_hash, record.block_number);
+                    Err(Error::EthChainReorg)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{FullBlock, Receipt};
+
+    /// The chain with the blocks `0..=head`, the block hash is the repeated byte of the number.
+    struct Chain {
+        head: u64,
+    }
+
+    impl TxSource for Chain {
+        fn get_block_number(&self) -> Result<u64> {
+            Ok(self.head)
+        }
+
+        fn get_tx_by_hash(&self, _hash: H256) -> Result<FullTransaction> {
+            Err(Error::NonExistentEthTx)
+        }
+
+        fn get_block_by_number(&self, number: u64) -> Result<FullBlock> {
+            if number > self.head {
+                return Err(Error::NonExistentEthBlock);
+            }
+            Ok(FullBlock {
+                number: Some(number.into()),
+                hash: Some(H256::repeat_byte(number as u8)),
+                ..Default::default()
+            })
+        }
+
+        fn get_receipt(&self, _hash: H256) -> Result<Receipt> {
+            Err(Error::NonExistentEthTx)
+        }
+    }
+
+    #[test]
+    fn test_check_confirmations() {
+        let chain = Chain { head: 20 };
+        let mut tx = FullTransaction::default();
+        match check_confirmations(&chain, &tx, 12) {
+            Err(Error::PendingConfirmations(0, 12)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        tx.block_number = Some(10.into());
+        assert_eq!(check_confirmations(&chain, &tx, 11).unwrap(), 11);
+        match check_confirmations(&chain, &tx, 12) {
+            Err(Error::PendingConfirmations(11, 12)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert_eq!(confirmations(9, 10), 0);
+    }
+
+    #[test]
+    fn test_check_canonical() {
+        let chain = Chain { head: 20 };
+        check_canonical(&chain, 10, H256::repeat_byte(10)).unwrap();
+        match check_canonical(&chain, 10, H256::repeat_byte(11)) {
+            Err(Error::EthChainReorg) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
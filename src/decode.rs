@@ -5,6 +5,9 @@ use serde::{Deserialize, Serialize};
 
 use chainx_primitives::{Balance, Index};
 
+/// The index of `AssetType::Free` in the ChainX runtime.
+const ASSET_TYPE_FREE: u8 = 0;
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct DecodeWrapper(substrate_primitives::storage::StorageData);
 
@@ -18,7 +21,21 @@ impl DecodeWrapper {
     pub fn nonce(self) -> Option<Index> {
         Decode::decode(&mut (self.0).0.as_slice())
     }
-    pub fn balance(self) -> Option<Balance> {
+    /// The free balance of `XAssets::AssetBalance`, which is a `CodecBTreeMap<AssetType, Balance>`.
+    pub fn free_balance(self) -> Option<Balance> {
+        let balances = self.asset_balances()?;
+        let free = balances.iter().find(|(asset_type, _)| *asset_type == ASSET_TYPE_FREE);
+        Some(free.map(|(_, balance)| *balance).unwrap_or(0))
+    }
+    #[cfg(test)]
+    pub fn bytes(self) -> Option<Vec<u8>> {
+        Decode::decode(&mut (self.0).0.as_slice())
+    }
+    #[cfg(test)]
+    pub fn compact_index(self) -> Option<parity_codec::Compact<Index>> {
+        Decode::decode(&mut (self.0).0.as_slice())
+    }
+    fn asset_balances(self) -> Option<Vec<(u8, Balance)>> {
         Decode::decode(&mut (self.0).0.as_slice())
     }
 }
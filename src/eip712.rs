@@ -0,0 +1,159 @@
+use serde::{Deserialize, Serialize};
+
+use chainx_primitives::AccountId;
+
+use crate::error::{Error, Result};
+use crate::eth::{check_tx_data, ecdsa_recover_address, parse_signature};
+use crate::types::{keccak, H160, H256, U256};
+
+const EIP712_DOMAIN_TYPE: &str = "EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)";
+const CLAIM_TYPE: &str = "Claim(string chainxAccount,uint256 amount,uint256 nonce)";
+
+pub static SDOT_CLAIM_NAME: &str = "ChainX SDOT";
+pub static SDOT_CLAIM_VERSION: &str = "1";
+
+/// The EIP-712 domain of the claim.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Eip712Domain {
+    pub name: String,
+    pub version: String,
+    pub chain_id: U256,
+    pub verifying_contract: H160,
+}
+
+impl Eip712Domain {
+    /// The domain of the SDOT claim on the chain.
+    pub fn sdot(chain_id: u64, verifying_contract: H160) -> Self {
+        Self {
+            name: SDOT_CLAIM_NAME.to_string(),
+            version: SDOT_CLAIM_VERSION.to_string(),
+            chain_id: chain_id.into(),
+            verifying_contract,
+        }
+    }
+
+    pub fn separator(&self) -> H256 {
+        let mut encoded = Vec::with_capacity(32 * 5);
+        encoded.extend_from_slice(keccak(EIP712_DOMAIN_TYPE).as_bytes());
+        encoded.extend_from_slice(keccak(&self.name).as_bytes());
+        encoded.extend_from_slice(keccak(&self.version).as_bytes());
+        encoded.extend_from_slice(&u256_word(self.chain_id));
+        encoded.extend_from_slice(&address_word(self.verifying_contract));
+        keccak(encoded)
+    }
+}
+
+/// `Claim(string chainxAccount,uint256 amount,uint256 nonce)`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Claim {
+    /// The ChainX account in SS58 format.
+    pub chainx_account: String,
+    pub amount: U256,
+    pub nonce: U256,
+}
+
+impl Claim {
+    pub fn struct_hash(&self) -> H256 {
+        let mut encoded = Vec::with_capacity(32 * 4);
+        encoded.extend_from_slice(keccak(CLAIM_TYPE).as_bytes());
+        encoded.extend_from_slice(keccak(&self.chainx_account).as_bytes());
+        encoded.extend_from_slice(&u256_word(self.amount));
+        encoded.extend_from_slice(&u256_word(self.nonce));
+        keccak(encoded)
+    }
+
+    /// The signed hash: `keccak256("\x19\x01" ‖ domainSeparator ‖ hashStruct(claim))`.
+    pub fn signing_hash(&self, domain: &Eip712Domain) -> H256 {
+        let mut encoded = Vec::with_capacity(2 + 32 * 2);
+        encoded.extend_from_slice(b"\x19\x01");
+        encoded.extend_from_slice(domain.separator().as_bytes());
+        encoded.extend_from_slice(self.struct_hash().as_bytes());
+        keccak(encoded)
+    }
+}
+
+/// Check the EIP-712 claim signed by the DOT owner, the domain must match the configured one.
+pub fn check_typed_claim(
+    expected: &Eip712Domain,
+    domain: &Eip712Domain,
+    claim: &Claim,
+    signature: &[u8],
+    claimed: H160,
+) -> Result<(AccountId, H160)> {
+    if domain != expected {
+        warn!("EIP-712 domain {:?} does not match {:?}", domain, expected);
+        return Err(Error::InvalidClaimDomain);
+    }
+    let signature = parse_signature(signature)?;
+    let eth_addr = ecdsa_recover_address(&signature, &claim.signing_hash(domain).to_fixed_bytes());
+    if eth_addr != Some(claimed.to_fixed_bytes()) {
+        warn!("Recover {:?} from the EIP-712 claim, expected {:?}", eth_addr, claimed);
+        return Err(Error::InvalidEthMessageSignature);
+    }
+
+    let who = check_tx_data(claim.chainx_account.as_bytes())?;
+    Ok((who, claimed))
+}
+
+fn u256_word(value: U256) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    value.to_big_endian(&mut word);
+    word
+}
+
+fn address_word(address: H160) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[12..].copy_from_slice(address.as_bytes());
+    word
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+
+    #[test]
+    fn test_domain_separator() {
+        // The example of EIP-712
+        let domain = Eip712Domain {
+            name: "Ether Mail".to_string(),
+            version: "1".to_string(),
+            chain_id: 1.into(),
+            verifying_contract: H160::repeat_byte(0xcc),
+        };
+        assert_eq!(
+            domain.separator(),
+            H256::from(hex!("f2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f"))
+        );
+    }
+
+    #[test]
+    fn test_check_typed_claim() {
+        let domain = Eip712Domain::sdot(1, H160::repeat_byte(0x42));
+        let claim = Claim {
+            chainx_account: "5UdrXD14mzNMnosk5PAYVTbWjFKrMwhjWuicLRGU3M8JcYBg".to_string(),
+            amount: 1000.into(),
+            nonce: 1.into(),
+        };
+        // eth_signTypedData by the key 0x4646..46
+        let signature = hex!("e9e369b72ef9fa2a68592f9840b6df4828daa441665551296d8427e367820ff151b2bbe4e33095dc4534934a3ae7c3ec8e7625e94e19844f032dd0522bdd8dbb1b");
+        let owner = H160::from(hex!("9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f"));
+
+        let (who, from) = check_typed_claim(&domain, &domain, &claim, &signature, owner).unwrap();
+        assert_eq!(from, owner);
+        assert_eq!(who, check_tx_data(claim.chainx_account.as_bytes()).unwrap());
+
+        let other_chain = Eip712Domain::sdot(3, H160::repeat_byte(0x42));
+        match check_typed_claim(&domain, &other_chain, &claim, &signature, owner) {
+            Err(Error::InvalidClaimDomain) => {}
+            other => panic!("unexpected result: {:?}", other.map(|(_, from)| from)),
+        }
+        let other_amount = Claim {
+            amount: 1001.into(),
+            ..claim
+        };
+        assert!(check_typed_claim(&domain, &domain, &other_amount, &signature, owner).is_err());
+    }
+}
@@ -0,0 +1,351 @@
+use lazy_static::lazy_static;
+
+use chainx_primitives::AccountId;
+
+use crate::error::{Error, Result};
+use crate::eth::{check_receipt, check_tx_data, check_tx_sender};
+use crate::types::{keccak, Bloom, Bytes, FullTransaction, Log, Receipt, H160, H256, U256};
+
+lazy_static! {
+    /// `transfer(address,uint256)`
+    pub static ref TRANSFER_SELECTOR: [u8; 4] = selector("transfer(address,uint256)");
+    /// `transferFrom(address,address,uint256)`
+    pub static ref TRANSFER_FROM_SELECTOR: [u8; 4] = selector("transferFrom(address,address,uint256)");
+    /// `Transfer(address,address,uint256)`
+    pub static ref TRANSFER_TOPIC: H256 = keccak("Transfer(address,address,uint256)");
+}
+
+/// The first 4 bytes of the keccak hash of the function signature.
+fn selector(signature: &str) -> [u8; 4] {
+    let mut selector = [0u8; 4];
+    selector.copy_from_slice(&keccak(signature).as_bytes()[..4]);
+    selector
+}
+
+/// The ERC-20 transfer call decoded from the transaction input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Erc20Call {
+    Transfer { to: H160, amount: U256 },
+    TransferFrom { from: H160, to: H160, amount: U256 },
+}
+
+impl Erc20Call {
+    pub fn decode(input: &Bytes) -> Result<Self> {
+        let input = &input.0;
+        if input.len() < 4 {
+            return Err(Error::EthTxInvalidData);
+        }
+        let (selector, args) = input.split_at(4);
+        if selector == &TRANSFER_SELECTOR[..] {
+            Ok(Erc20Call::Transfer {
+                to: decode_address(args, 0)?,
+                amount: decode_uint(args, 1)?,
+            })
+        } else if selector == &TRANSFER_FROM_SELECTOR[..] {
+            Ok(Erc20Call::TransferFrom {
+                from: decode_address(args, 0)?,
+                to: decode_address(args, 1)?,
+                amount: decode_uint(args, 2)?,
+            })
+        } else {
+            Err(Error::EthTxInvalidData)
+        }
+    }
+
+    pub fn to(&self) -> H160 {
+        match *self {
+            Erc20Call::Transfer { to, .. } | Erc20Call::TransferFrom { to, .. } => to,
+        }
+    }
+
+    pub fn amount(&self) -> U256 {
+        match *self {
+            Erc20Call::Transfer { amount, .. } | Erc20Call::TransferFrom { amount, .. } => amount,
+        }
+    }
+
+    /// The bytes appended to the call arguments, which carry the ChainX account of the mapping,
+    /// the input must be the one the call is decoded from.
+    pub fn memo<'a>(&self, input: &'a Bytes) -> &'a [u8] {
+        let args = match self {
+            Erc20Call::Transfer { .. } => 2,
+            Erc20Call::TransferFrom { .. } => 3,
+        };
+        &input.0[4 + 32 * args..]
+    }
+}
+
+/// The `Transfer(address indexed from, address indexed to, uint256 value)` event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransferEvent {
+    /// The token contract which emits the event.
+    pub token: H160,
+    pub from: H160,
+    pub to: H160,
+    pub amount: U256,
+}
+
+impl TransferEvent {
+    /// Parse the log, `None` if it is not a `Transfer` event.
+    pub fn from_log(log: &Log) -> Option<Self> {
+        if log.topics.len() != 3 || log.topics[0] != *TRANSFER_TOPIC || log.data.0.len() != 32 {
+            return None;
+        }
+        Some(TransferEvent {
+            token: log.address,
+            from: decode_address(log.topics[1].as_bytes(), 0).ok()?,
+            to: decode_address(log.topics[2].as_bytes(), 0).ok()?,
+            amount: U256::from_big_endian(&log.data.0),
+        })
+    }
+}
+
+/// Check the transaction transfers the `token` to the `deposit` address,
+/// and the receipt has the corresponding `Transfer` event.
+///
+/// The tokens must be transferred from the sender itself, `transferFrom` by an approved spender
+/// would map the tokens of another owner to the account chosen by the spender.
+///
+/// Return the token owner and the transferred amount.
+pub fn check_token_transfer(tx: &FullTransaction, receipt: &Receipt, token: H160, deposit: H160) -> Result<(H160, U256)> {
+    if tx.to != Some(token) {
+        return Err(Error::InvalidTokenTransfer);
+    }
+    let call = Erc20Call::decode(&tx.input)?;
+    let owner = tx.from;
+    if let Erc20Call::TransferFrom { from, .. } = call {
+        if from != owner {
+            warn!("Ethereum tx {:?} transfers the tokens of {:?} by the spender {:?}", tx.hash, from, owner);
+            return Err(Error::InvalidTokenTransfer);
+        }
+    }
+    if call.to() != deposit {
+        return Err(Error::InvalidTokenTransfer);
+    }
+
+    let expected = TransferEvent {
+        token,
+        from: owner,
+        to: deposit,
+        amount: call.amount(),
+    };
+    if !receipt.logs.iter().filter_map(TransferEvent::from_log).any(|event| event == expected) {
+        warn!("No Transfer event {:?} in the receipt of tx {:?}", expected, tx.hash);
+        return Err(Error::InvalidTokenTransfer);
+    }
+    Ok((owner, expected.amount))
+}
+
+/// Whether the logs bloom may contain the `Transfer` event of the `token` to the address `to`.
+///
+/// `false` means the block has no such event for sure, so the receipts need not be fetched.
+pub fn may_contain_transfer(bloom: &Bloom, token: H160, to: H160) -> bool {
+    bloom.contains_input(token.as_bytes())
+        && bloom.contains_input(TRANSFER_TOPIC.as_bytes())
+        && bloom.contains_input(H256::from(to).as_bytes())
+}
+
+/// Check the mapping transaction which transfers the `token` to the `deposit` address,
+/// the ChainX account is in the memo appended to the transfer call.
+///
+/// Return the ChainX account and the token owner.
+pub fn check_token_tx(tx: FullTransaction, receipt: &Receipt, token: H160, deposit: H160) -> Result<(AccountId, H160)> {
+    check_receipt(&tx.hash, receipt)?;
+    let (owner, _) = check_token_transfer(&tx, receipt, token, deposit)?;
+    let who = check_tx_data(Erc20Call::decode(&tx.input)?.memo(&tx.input))?;
+    check_tx_sender(tx)?;
+    Ok((who, owner))
+}
+
+/// Decode the `index`-th 32 bytes word as address, the high 12 bytes must be zero.
+fn decode_address(args: &[u8], index: usize) -> Result<H160> {
+    let word = word_at(args, index)?;
+    if word[..12].iter().any(|b| *b != 0) {
+        return Err(Error::EthTxInvalidData);
+    }
+    Ok(H160::from_slice(&word[12..]))
+}
+
+fn decode_uint(args: &[u8], index: usize) -> Result<U256> {
+    Ok(U256::from_big_endian(word_at(args, index)?))
+}
+
+fn word_at(args: &[u8], index: usize) -> Result<&[u8]> {
+    match args.get(index * 32..(index + 1) * 32) {
+        Some(word) => Ok(word),
+        None => Err(Error::EthTxInvalidData),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+
+    fn word(address: H160) -> Vec<u8> {
+        let mut word = vec![0u8; 12];
+        word.extend_from_slice(address.as_bytes());
+        word
+    }
+
+    fn amount_word(amount: u64) -> Vec<u8> {
+        let mut word = [0u8; 32];
+        U256::from(amount).to_big_endian(&mut word);
+        word.to_vec()
+    }
+
+    #[test]
+    fn test_selectors() {
+        assert_eq!(*TRANSFER_SELECTOR, hex!("a9059cbb"));
+        assert_eq!(*TRANSFER_FROM_SELECTOR, hex!("23b872dd"));
+        assert_eq!(
+            *TRANSFER_TOPIC,
+            H256::from(hex!("ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"))
+        );
+    }
+
+    #[test]
+    fn test_check_token_transfer() {
+        let token = H160::repeat_byte(0x10);
+        let deposit = H160::repeat_byte(0x20);
+        let owner = H160::repeat_byte(0x30);
+
+        let mut input = TRANSFER_SELECTOR.to_vec();
+        input.extend(word(deposit));
+        input.extend(amount_word(500));
+        let tx = FullTransaction {
+            from: owner,
+            to: Some(token),
+            input: input.into(),
+            ..Default::default()
+        };
+        assert_eq!(
+            Erc20Call::decode(&tx.input).unwrap(),
+            Erc20Call::Transfer {
+                to: deposit,
+                amount: 500.into()
+            }
+        );
+
+        let log = Log {
+            address: token,
+            topics: vec![*TRANSFER_TOPIC, H256::from(owner), H256::from(deposit)],
+            data: amount_word(500).into(),
+            ..Default::default()
+        };
+        let mut receipt = Receipt {
+            logs: vec![log],
+            ..Default::default()
+        };
+        assert_eq!(check_token_transfer(&tx, &receipt, token, deposit).unwrap(), (owner, 500.into()));
+        assert!(check_token_transfer(&tx, &receipt, token, owner).is_err());
+        assert!(check_token_transfer(&tx, &receipt, deposit, deposit).is_err());
+
+        // the owner moves its own tokens by `transferFrom`
+        let mut input = TRANSFER_FROM_SELECTOR.to_vec();
+        input.extend(word(owner));
+        input.extend(word(deposit));
+        input.extend(amount_word(500));
+        let transfer_from = FullTransaction {
+            input: input.into(),
+            ..tx.clone()
+        };
+        assert_eq!(check_token_transfer(&transfer_from, &receipt, token, deposit).unwrap(), (owner, 500.into()));
+        // an approved spender cannot map the tokens of the owner
+        let spender = FullTransaction {
+            from: H160::repeat_byte(0x40),
+            ..transfer_from
+        };
+        match check_token_transfer(&spender, &receipt, token, deposit) {
+            Err(Error::InvalidTokenTransfer) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        // the amount of the event does not match the call
+        receipt.logs[0].data = amount_word(499).into();
+        assert!(check_token_transfer(&tx, &receipt, token, deposit).is_err());
+    }
+
+    #[test]
+    fn test_check_token_tx() {
+        let token = H160::repeat_byte(0x10);
+        let deposit = H160::repeat_byte(0x20);
+        let account = "5UdrXD14mzNMnosk5PAYVTbWjFKrMwhjWuicLRGU3M8JcYBg";
+        // EIP-155 tx calling `transfer(deposit, 500)` with the memo `account`, signed by the key 0x4646..46
+        let tx = FullTransaction {
+            from: H160::from(hex!("9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f")),
+            to: Some(token),
+            gas: 60000.into(),
+            gas_price: 20_000_000_000u64.into(),
+            hash: H256::from(hex!("39f0a5c8c217f49bf65462c6c7c052102f2084c4cbef6ac2664ba013de08f806")),
+            nonce: 7.into(),
+            input: hex!("a9059cbb000000000000000000000000202020202020202020202020202020202020202000000000000000000000000000000000000000000000000000000000000001f435556472584431346d7a4e4d6e6f736b35504159565462576a464b724d77686a577569634c524755334d384a63594267").to_vec().into(),
+            r: Some(U256::from_big_endian(&hex!("afcfc0b9fc53dcbce1828b01b600383577fad40e14cccd1fa11bf1aa8ea9063a"))),
+            s: Some(U256::from_big_endian(&hex!("178a44321c28400d1faad35ae8db5654955e01757d1349b7d44afadf4b5cbb58"))),
+            v: Some(38.into()),
+            ..Default::default()
+        };
+        let call = Erc20Call::decode(&tx.input).unwrap();
+        assert_eq!(call.memo(&tx.input), account.as_bytes());
+
+        let log = Log {
+            address: token,
+            topics: vec![*TRANSFER_TOPIC, H256::from(tx.from), H256::from(deposit)],
+            data: amount_word(500).into(),
+            ..Default::default()
+        };
+        let mut bloom = Bloom::zero();
+        bloom.accrue(log.address.as_bytes());
+        for topic in &log.topics {
+            bloom.accrue(topic.as_bytes());
+        }
+        let receipt = Receipt {
+            transaction_hash: tx.hash,
+            status: Some(1.into()),
+            logs: vec![log],
+            logs_bloom: bloom,
+            ..Default::default()
+        };
+        assert!(may_contain_transfer(&receipt.logs_bloom, token, deposit));
+        assert!(!may_contain_transfer(&receipt.logs_bloom, token, H160::repeat_byte(0x30)));
+        assert!(!may_contain_transfer(&Bloom::zero(), token, deposit));
+
+        let (who, owner) = check_token_tx(tx.clone(), &receipt, token, deposit).unwrap();
+        assert_eq!(owner, tx.from);
+        assert_eq!(who, check_tx_data(account.as_bytes()).unwrap());
+
+        let unsigned = FullTransaction {
+            r: None,
+            s: None,
+            v: None,
+            ..tx.clone()
+        };
+        match check_token_tx(unsigned, &receipt, token, deposit) {
+            Err(Error::InvalidEthTxSignature) => {}
+            other => panic!("unexpected result: {:?}", other.map(|(_, owner)| owner)),
+        }
+        // the transfer has no memo
+        let mut input = tx.input.0.clone();
+        input.truncate(4 + 32 * 2);
+        let no_memo = FullTransaction {
+            input: input.into(),
+            ..tx
+        };
+        match check_token_tx(no_memo, &receipt, token, deposit) {
+            Err(Error::EthTxInvalidData) => {}
+            other => panic!("unexpected result: {:?}", other.map(|(_, owner)| owner)),
+        }
+    }
+
+    #[test]
+    fn test_decode_invalid_call() {
+        let mut input = TRANSFER_FROM_SELECTOR.to_vec();
+        input.extend(word(H160::repeat_byte(1)));
+        input.extend(word(H160::repeat_byte(2)));
+        assert!(Erc20Call::decode(&input.clone().into()).is_err());
+        input.extend(amount_word(1));
+        assert!(Erc20Call::decode(&input.clone().into()).is_ok());
+        input[4] = 1;
+        assert!(Erc20Call::decode(&input.into()).is_err());
+    }
+}
@@ -1,3 +1,6 @@
+// The `Fail` derive of failure defines its impls inside a constant.
+#![allow(non_local_definitions)]
+
 use failure::Fail;
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -20,18 +23,50 @@ pub enum Error {
     Reqwest(reqwest::Error),
     #[fail(display = "Rpc internal error: {}", _0)]
     Web3Rpc(#[cause] web3::Error),
+    #[fail(display = "Ethereum JSON-RPC error {}: {}", _0, _1)]
+    EthRpc(i64, String),
     #[fail(display = "EtherScanApi get Ethereum tx error")]
     EtherScanCannotGetTx,
     #[fail(display = "Non-existent Ethereum transaction")]
     NonExistentEthTx,
+    #[fail(display = "Non-existent Ethereum block")]
+    NonExistentEthBlock,
     #[fail(display = "Invalid Ethereum transaction signature")]
     InvalidEthTxSignature,
+    #[fail(display = "Invalid Ethereum message signature")]
+    InvalidEthMessageSignature,
+    #[fail(display = "EIP-712 domain of the claim does not match")]
+    InvalidClaimDomain,
+    #[fail(display = "Ethereum transaction failed or reverted")]
+    EthTxFailed,
+    #[fail(display = "Pending Ethereum transaction confirmations: {}/{}", _0, _1)]
+    PendingConfirmations(u64, u64),
+    #[fail(display = "Ethereum block of the transaction has been reorganized")]
+    EthChainReorg,
+    #[fail(display = "Invalid Ethereum block data")]
+    InvalidEthBlock,
+    #[fail(display = "Ethereum transaction is not included in the block")]
+    EthTxNotInBlock,
+    #[fail(display = "Invalid Merkle-Patricia inclusion proof")]
+    InvalidEthProof,
     #[fail(display = "Invalid Ethereum transaction data field")]
     EthTxInvalidData,
+    #[fail(display = "Invalid ERC-20 transfer to the deposit address")]
+    InvalidTokenTransfer,
     #[fail(display = "You are NOT the DOT owner or You Have received the SDOT")]
     NoSdot,
+    #[fail(display = "You are NOT the DOT owner")]
+    NotDotOwner,
     #[fail(display = "Mapping SDOT timeout")]
     MappingTimeout,
+    #[fail(display = "ChainX node error: {}", _0)]
+    ChainXRpc(String),
+    #[fail(display = "Invalid SDOT payout: {}", _0)]
+    InvalidPayout(String),
+    #[fail(display = "Invalid DOT owner snapshot: {}", _0)]
+    InvalidSnapshot(String),
+    #[fail(display = "Invalid scanner checkpoint: {}", _0)]
+    InvalidCheckpoint(String),
 }
 
 impl From<std::fmt::Error> for Error {
@@ -102,6 +137,23 @@ impl From<Error> for jsonrpc_core::Error {
             Error::EthTxInvalidData => rpc_error(ERROR + 3, "Invalid Ethereum transaction data field"),
             Error::NoSdot => rpc_error(ERROR + 4, "You are NOT the DOT owner or You Have received the SDOT"),
             Error::MappingTimeout => rpc_error(ERROR + 5, "Mapping SDOT timeout"),
+            Error::NonExistentEthBlock => rpc_error(ERROR + 6, "Non-existent Ethereum block"),
+            Error::InvalidEthMessageSignature => rpc_error(ERROR + 7, "Invalid Ethereum message signature"),
+            Error::InvalidClaimDomain => rpc_error(ERROR + 8, "EIP-712 domain of the claim does not match"),
+            Error::InvalidTokenTransfer => rpc_error(ERROR + 9, "Invalid ERC-20 transfer to the deposit address"),
+            Error::EthTxFailed => rpc_error(ERROR + 10, "Ethereum transaction failed or reverted"),
+            Error::PendingConfirmations(confirmations, required) => rpc_error(
+                ERROR + 11,
+                format!("Pending Ethereum transaction confirmations: {}/{}", confirmations, required),
+            ),
+            Error::EthChainReorg => rpc_error(ERROR + 12, "Ethereum block of the transaction has been reorganized"),
+            Error::InvalidEthBlock => rpc_error(ERROR + 13, "Invalid Ethereum block data"),
+            Error::EthTxNotInBlock => rpc_error(ERROR + 14, "Ethereum transaction is not included in the block"),
+            Error::InvalidEthProof => rpc_error(ERROR + 15, "Invalid Merkle-Patricia inclusion proof"),
+            Error::ChainXRpc(msg) => rpc_error(ERROR + 16, format!("ChainX node error: {}", msg)),
+            Error::InvalidPayout(msg) => rpc_error(ERROR + 17, format!("Invalid SDOT payout: {}", msg)),
+            Error::EthRpc(code, msg) => rpc_error(ERROR + 18, format!("Ethereum JSON-RPC error {}: {}", code, msg)),
+            Error::NotDotOwner => rpc_error(ERROR + 19, "You are NOT the DOT owner"),
             _ => jsonrpc_core::Error::internal_error(),
         }
     }
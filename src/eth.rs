@@ -1,114 +1,129 @@
 use parity_codec::Encode;
-use serde::Deserialize;
 use substrate_primitives::crypto::Ss58Codec;
-use substrate_primitives::ed25519::{Pair, Public};
+use substrate_primitives::ed25519::Public;
 
 use chainx_primitives::AccountId;
 
 use crate::error::{Error, Result};
-use crate::types::{FullTransaction, EthereumAddress, Block, EcdsaSignature, UnverifiedTransaction, H160, H256};
-
-pub static ETHERSCAN_API: &str = "http://api-cn.etherscan.com/api?module=proxy&action=eth_getTransactionByHash&apikey=WEPDGZ6U6GQ2RD4AGZNUV7CJ25C44KQQAJ&txhash=";
-pub static ETHERSCAN_GETBLOCKTX_API: &str = "http://api-cn.etherscan.com/api?module=proxy&action=eth_getBlockByNumber&apikey=WEPDGZ6U6GQ2RD4AGZNUV7CJ25C44KQQAJ&boolean=true&tag=";
-
-#[derive(Deserialize, Debug)]
-struct EtherScanApiResult {
-    id: u64,
-    jsonrpc: String,
-    result: Option<FullTransaction>,
-}
-
-#[derive(Deserialize, Debug)]
-struct EtherScanResult {
-    id: u64,
-    jsonrpc: String,
-    result: Option<Block>,
-}
-
-#[derive(Clone)]
-pub struct EtherScanApi(reqwest::Client);
-
-impl EtherScanApi {
-    pub fn new() -> Self {
-        Self(reqwest::Client::new())
-    }
-
-    pub fn get_tx_by_hash(&self, hash: H256) -> Result<FullTransaction> {
-        match self.get_tx_by_hash_impl(hash) {
-            Ok(transaction) => match transaction {
-                Some(transaction) => Ok(transaction),
-                None => {
-                    warn!("Non-existent Ethereum transaction");
-                    Err(Error::NonExistentEthTx.into())
-                }
-            },
-            Err(err) => {
-                error!("EtherScanApi get Ethereum tx error: {:?}", err);
-                Err(Error::EtherScanCannotGetTx.into())
-            }
-        }
-    }
+use crate::ledger::Ledger;
+use crate::types::{keccak, FullTransaction, EthereumAddress, EcdsaSignature, Receipt, UnverifiedTransaction, H160, H256};
 
-    pub fn get_tx_by_block_num(&self, block_num: u32)  {
-//        match self.get_tx_by_block_impl(block_num) {
-//            Ok(transaction) => match transaction {
-//                Some(transaction) => Ok(transaction),
-//                None => {
-//                    warn!("Non-existent Ethereum transaction");
-//                    Err(Error::NonExistentEthTx.into())
-//                }
-//            },
-//            Err(err) => {
-//                error!("EtherScanApi get Ethereum tx error: {:?}", err);
-//                Err(Error::EtherScanCannotGetTx.into())
-//            }
-//        }
-    }
+pub fn check_tx(tx: FullTransaction, receipt: &Receipt) -> Result<(AccountId, H160)> {
+    check_receipt(&tx.hash, receipt)?;
+    let data = tx.input.0.clone();
+    let from = check_tx_sender(tx)?;
+
+    let who = check_tx_data(&data)?;
+    Ok((who, from))
+}
 
-    pub fn get_tx_by_hash_impl(&self, hash: H256) -> Result<Option<FullTransaction>> {
-        let url = format!("{}{:?}", ETHERSCAN_API, hash);
-        let result = self.0.get(&url).send()?.json::<EtherScanApiResult>()?;
-        Ok(result.result)
+/// Check the signature of the transaction is signed by its sender, return the sender.
+pub fn check_tx_sender(tx: FullTransaction) -> Result<H160> {
+    if !tx.is_signed() {
+        warn!("Ethereum tx {:?} has no signature", tx.hash);
+        return Err(Error::InvalidEthTxSignature);
     }
+    let (from, raw, data, r, s, v) = parse_tx(tx);
+    let signature = EcdsaSignature(r, s, v as i8);
+    check_tx_signature(&signature, &raw, &data, from)?;
+    Ok(from)
+}
 
-    pub fn get_tx_by_block_impl(&self, block_num: u32) {
-        let url = format!("{}{:#x}", ETHERSCAN_GETBLOCKTX_API, block_num);
-        println!("url:{:?}", url);
-        let result = self.0.get(&url).send().unwrap().json::<EtherScanResult>().unwrap();
-        println!("{:?}", result)
+/// Check the transaction, and reject it if the Ethereum address or the transaction has been mapped.
+pub fn check_tx_with_ledger(tx: FullTransaction, receipt: &Receipt, ledger: &Ledger) -> Result<(AccountId, H160)> {
+    let hash = tx.hash;
+    let (who, from) = check_tx(tx, receipt)?;
+    ledger.check(&from, &hash)?;
+    Ok((who, from))
+}
+
+/// Check the receipt belongs to the transaction, and the transaction did not fail or revert.
+pub fn check_receipt(hash: &H256, receipt: &Receipt) -> Result<()> {
+    if receipt.transaction_hash != *hash {
+        warn!("Receipt of tx {:?} does not match tx {:?}", receipt.transaction_hash, hash);
+        return Err(Error::NonExistentEthTx);
+    }
+    if receipt.is_failed() {
+        warn!("Ethereum tx {:?} failed, gas used: {}", hash, receipt.gas_used);
+        return Err(Error::EthTxFailed);
     }
+    Ok(())
 }
 
-pub fn check_tx(tx: FullTransaction) -> Result<(AccountId, H160)> {
-    let (from, raw, data, r, s, v) = parse_tx(tx);
-    /*println!(
-        "Transaction content: raw[0x{}], data [0x{}], r [0x{}], s [0x{}], v [{}]",
-        hex::encode(&raw),
-        hex::encode(&data),
-        hex::encode(&r),
-        hex::encode(&s),
-        v
-    );
-    */
+/// Check the raw signed transaction pasted from the wallet, without a block explorer.
+///
+/// Return the transaction hash, the ChainX account and the recovered Ethereum sender.
+pub fn check_raw_tx(raw_tx: &[u8]) -> Result<(H256, AccountId, H160)> {
+    let tx = UnverifiedTransaction::decode_raw(raw_tx)?;
+    let (raw, data, r, s, v) = parse_unverified_tx(&tx);
     let signature = EcdsaSignature(r, s, v as i8);
-    check_tx_signature(&signature, &raw, &data, from)?;
+    let from = match eth_recover(&signature, &raw) {
+        Some(from) => H160::from(from),
+        None => return Err(Error::InvalidEthTxSignature),
+    };
+    info!("Recover the sender {:?} of Ethereum tx {:?}", from, tx.hash);
 
     let who = check_tx_data(&data)?;
-    Ok((who, from))
+    Ok((tx.hash, who, from))
+}
+
+/// Check the message signed by `personal_sign` (EIP-191) as an alternative proof of the DOT ownership.
+///
+/// The message has the same format as the data field of the mapping transaction,
+/// and the signature is 65 bytes `r || s || v`.
+pub fn check_signed_message(message: &[u8], signature: &[u8], claimed: H160) -> Result<(AccountId, H160)> {
+    let signature = parse_signature(signature)?;
+    let eth_addr = eth_recover(&signature, &personal_message(message));
+    if eth_addr != Some(claimed.to_fixed_bytes()) {
+        warn!("Recover {:?} from the signed message, expected {:?}", eth_addr, claimed);
+        return Err(Error::InvalidEthMessageSignature);
+    }
+
+    let who = check_tx_data(message)?;
+    Ok((who, claimed))
+}
+
+/// The hash signed by `personal_sign`.
+pub fn personal_message_hash(message: &[u8]) -> H256 {
+    keccak(personal_message(message))
+}
+
+/// Prefix the message with `"\x19Ethereum Signed Message:\n" + len(message)`.
+fn personal_message(message: &[u8]) -> Vec<u8> {
+    let mut prefixed = format!("\x19Ethereum Signed Message:\n{}", message.len()).into_bytes();
+    prefixed.extend_from_slice(message);
+    prefixed
+}
+
+/// Parse the 65 bytes signature `r || s || v`.
+pub fn parse_signature(signature: &[u8]) -> Result<EcdsaSignature> {
+    if signature.len() != 65 {
+        return Err(Error::InvalidEthMessageSignature);
+    }
+    let mut r = [0u8; 32];
+    let mut s = [0u8; 32];
+    r.copy_from_slice(&signature[..32]);
+    s.copy_from_slice(&signature[32..64]);
+    Ok(EcdsaSignature(r, s, signature[64] as i8))
 }
 
 fn parse_tx(tx: FullTransaction) -> (H160, Vec<u8>, Vec<u8>, [u8; 32], [u8; 32], u8) {
     let from = tx.from;
-    let unsigned_tx: UnverifiedTransaction = tx.clone().into();
-    let raw = unsigned_tx.raw_msg();
-    let data = unsigned_tx.data.0.clone();
-    println!("data: {:?}", data);
+    let unsigned_tx: UnverifiedTransaction = tx.into();
+    let (raw, data, r, s, standard_v) = parse_unverified_tx(&unsigned_tx);
+    (from, raw, data, r, s, standard_v)
+}
+
+fn parse_unverified_tx(tx: &UnverifiedTransaction) -> (Vec<u8>, Vec<u8>, [u8; 32], [u8; 32], u8) {
+    let raw = tx.raw_msg();
+    let data = tx.data.0.clone();
+    debug!("data: {:?}", data);
     let mut r = [0u8; 32];
     let mut s = [0u8; 32];
-    unsigned_tx.r.to_big_endian(&mut r[..]);
-    unsigned_tx.s.to_big_endian(&mut s[..]);
-    let standard_v = unsigned_tx.standard_v();
-    (from, raw, data, r, s, standard_v)
+    tx.r.to_big_endian(&mut r[..]);
+    tx.s.to_big_endian(&mut s[..]);
+    let standard_v = tx.standard_v();
+    (raw, data, r, s, standard_v)
 }
 
 fn check_tx_signature(
@@ -117,33 +132,69 @@ fn check_tx_signature(
     data: &[u8],
     from: H160,
 ) -> Result<()> {
-    let eth_addr = eth_recover(&signature, &raw);
-    if !contains(&raw, &data) || eth_addr.is_none() || eth_addr != Some(from.to_fixed_bytes()) {
-        return Err(Error::InvalidEthTxSignature.into());
+    // A plain transfer has no data to be found in the signed payload.
+    if data.is_empty() {
+        return Err(Error::EthTxInvalidData);
+    }
+    let eth_addr = eth_recover(signature, raw);
+    if !contains(raw, data) || eth_addr.is_none() || eth_addr != Some(from.to_fixed_bytes()) {
+        return Err(Error::InvalidEthTxSignature);
     }
     Ok(())
 }
 
-fn check_tx_data(data: &[u8]) -> Result<AccountId> {
-    let data = "5UdrXD14mzNMnosk5PAYVTbWjFKrMwhjWuicLRGU3M8JcYBg".as_bytes();
-    let public = std::str::from_utf8(data).unwrap();
+pub fn check_tx_data(data: &[u8]) -> Result<AccountId> {
+    let data = decode_tx_data(data)?;
+    // The data field format: `ss58 address[@memo[@extra]]`
+    let segments = split_tx_data(&data);
+    if segments.len() > 3 {
+        warn!("Too many segments in Ethereum tx data field: {}", segments.len());
+        return Err(Error::EthTxInvalidData);
+    }
+    let public = match std::str::from_utf8(&segments[0]) {
+        Ok(public) => public.trim(),
+        Err(_) => return Err(Error::EthTxInvalidData),
+    };
     let who = match Public::from_ss58check(public) {
         Ok(public) => AccountId::from_slice(public.as_slice()),
-        Err(_) => return Err(Error::EthTxInvalidData.into()),
+        Err(_) => return Err(Error::EthTxInvalidData),
     };
     info!("AccountId '{:?}' ss58 check result successfully", who);
     Ok(who)
 }
 
+/// Wallets put either the raw UTF-8 payload or the hex-encoded UTF-8 payload into the data field,
+/// return the raw UTF-8 payload for both cases.
+fn decode_tx_data(data: &[u8]) -> Result<Vec<u8>> {
+    let text = match std::str::from_utf8(data) {
+        Ok(text) => text.trim(),
+        Err(_) => return Err(Error::EthTxInvalidData),
+    };
+    if text.is_empty() {
+        return Err(Error::EthTxInvalidData);
+    }
+    let hex_text = text.trim_start_matches("0x");
+    if hex_text.len() % 2 == 0 && hex_text.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return hex::decode(hex_text).map_err(|_| Error::EthTxInvalidData);
+    }
+    Ok(text.as_bytes().to_vec())
+}
+
 fn eth_recover(s: &EcdsaSignature, sign_data: &[u8]) -> Option<EthereumAddress> {
     use tiny_keccak::keccak256;
-    let msg = keccak256(sign_data);
+    ecdsa_recover_address(s, &keccak256(sign_data))
+}
+
+/// Recover the Ethereum address from the signature of the 32 bytes message hash.
+pub fn ecdsa_recover_address(s: &EcdsaSignature, msg: &[u8; 32]) -> Option<EthereumAddress> {
+    use tiny_keccak::keccak256;
     let mut res = EthereumAddress::default();
-    res.copy_from_slice(&keccak256(&ecdsa_recover(s, &msg)?[..])[12..]);
+    res.copy_from_slice(&keccak256(&ecdsa_recover(s, msg)?[..])[12..]);
     Some(res)
 }
 
-fn ecdsa_recover(sig: &EcdsaSignature, msg: &[u8; 32]) -> Option<[u8; 64]> {
+/// Recover the uncompressed public key (without the `0x04` prefix) from the signature of the message hash.
+pub fn ecdsa_recover(sig: &EcdsaSignature, msg: &[u8; 32]) -> Option<[u8; 64]> {
     let msg = secp256k1::Message::parse(msg);
     let signature = secp256k1::Signature::parse_slice(&(sig.0, sig.1).encode()).ok()?;
     let recovery_id = if sig.2 > 26 { sig.2 - 27 } else { sig.2 };
@@ -165,25 +216,160 @@ fn split_tx_data(data: &[u8]) -> Vec<Vec<u8>> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::U256;
     use hex_literal::hex;
 
     #[test]
-    fn test_etherscan_txhash_api() {
-        let result = EtherScanApi::new().get_tx_by_hash(H256::from(&hex!(
-            "09146acd857bf292907934839f99ab41ecede9a4dbaacfcda043ddfde1f270d5"
-        )));
-        println!("result: {:?}", result);
+    fn test_check_tx_data() {
+        let address = "5UdrXD14mzNMnosk5PAYVTbWjFKrMwhjWuicLRGU3M8JcYBg";
+        let who = check_tx_data(address.as_bytes()).unwrap();
+
+        let with_memo = format!("{}@memo", address);
+        assert_eq!(check_tx_data(with_memo.as_bytes()).unwrap(), who);
+        let with_extra = format!("{}@memo@extra", address);
+        assert_eq!(check_tx_data(with_extra.as_bytes()).unwrap(), who);
 
-        let who = check_tx(result.unwrap()).unwrap();
-        println!("who: {:?}", who);
+        let hex_encoded = hex::encode(with_memo.as_bytes());
+        assert_eq!(check_tx_data(hex_encoded.as_bytes()).unwrap(), who);
+        let prefixed = format!("0x{}", hex_encoded);
+        assert_eq!(check_tx_data(prefixed.as_bytes()).unwrap(), who);
+    }
+
+    #[test]
+    fn test_check_invalid_tx_data() {
+        let address = "5UdrXD14mzNMnosk5PAYVTbWjFKrMwhjWuicLRGU3M8JcYBg";
+        assert!(check_tx_data(b"").is_err());
+        assert!(check_tx_data(&[0xff, 0xfe, 0x00]).is_err());
+        assert!(check_tx_data(&address.as_bytes()[1..]).is_err());
+        assert!(check_tx_data(format!("{}@a@b@c", address).as_bytes()).is_err());
+        assert!(check_tx_data(b"0x1234").is_err());
     }
 
     #[test]
-    fn test_etherscan_block_api() {
-        let result = EtherScanApi::new().get_tx_by_block_impl(5466);
-//        println!("result: {:?}", result);
-//
-//        let who = check_tx(result.unwrap()).unwrap();
-//        println!("who: {:?}", who);
+    fn test_check_raw_tx() {
+        // EIP-1559 tx with data `5UdrXD14mzNMnosk5PAYVTbWjFKrMwhjWuicLRGU3M8JcYBg@memo`, signed by the key 0x4646..46
+        let raw = hex::decode("02f8a7010784773594008506fc23ac0082ea6094353535353535353535353535353535353535353587038d7ea4c68000b535556472584431346d7a4e4d6e6f736b35504159565462576a464b724d77686a577569634c524755334d384a63594267406d656d6fc080a0ade7392a5881f5a6029615163868c34d4f23d81440e8382a6af3a93714f7a5d7a056558a1c88ceebaf904a6badf65c1acad9c6a78aae199c82692b9bfa92843df2").unwrap();
+        let (hash, who, from) = check_raw_tx(&raw).unwrap();
+        assert_eq!(
+            format!("{:x}", hash),
+            "e3f5740ecf7f7dc3948b22c3ad37022fed60fec8b9ffe2fedb767e418d6a3293"
+        );
+        assert_eq!(format!("{:x}", from), "9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f");
+        assert_eq!(who, check_tx_data(b"5UdrXD14mzNMnosk5PAYVTbWjFKrMwhjWuicLRGU3M8JcYBg").unwrap());
+
+        // the example of EIP-155 has empty data field
+        let raw = hex::decode("f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83").unwrap();
+        match check_raw_tx(&raw) {
+            Err(Error::EthTxInvalidData) => {}
+            other => panic!("unexpected result: {:?}", other.map(|(hash, _, from)| (hash, from))),
+        }
+        assert!(check_raw_tx(&raw[1..]).is_err());
+    }
+
+    #[test]
+    fn test_check_contract_creation_tx() {
+        // EIP-155 contract creation tx with data `5UdrXD14mzNMnosk5PAYVTbWjFKrMwhjWuicLRGU3M8JcYBg`,
+        // signed by the key 0x4646..46
+        let tx = FullTransaction {
+            from: H160::from(hex!("9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f")),
+            to: None,
+            gas: 60000.into(),
+            gas_price: 20_000_000_000u64.into(),
+            hash: H256::from(hex!("0bdde0556af573f7bf11520f0baa14289f4aba0f8b54c18b92e05100130b3e5b")),
+            nonce: 7.into(),
+            input: b"5UdrXD14mzNMnosk5PAYVTbWjFKrMwhjWuicLRGU3M8JcYBg".to_vec().into(),
+            r: Some(U256::from_big_endian(&hex!("06b23de7f9a9a4984461a9e2f6e10c5b631f21728cb3217c63ffc391c1f83d85"))),
+            s: Some(U256::from_big_endian(&hex!("6fa892b95f6666693b2c29b9e100e706dab3d246050a9a18543635083dacea25"))),
+            v: Some(38.into()),
+            value: 1_000_000_000_000_000u64.into(),
+            ..Default::default()
+        };
+        let mut receipt = Receipt {
+            transaction_hash: tx.hash,
+            status: Some(1.into()),
+            ..Default::default()
+        };
+        let (who, from) = check_tx(tx.clone(), &receipt).unwrap();
+        assert_eq!(from, tx.from);
+        assert_eq!(who, check_tx_data(&tx.input.0).unwrap());
+
+        // the receipt of other tx
+        let other = Receipt {
+            transaction_hash: H256::zero(),
+            ..receipt.clone()
+        };
+        assert!(check_tx(tx.clone(), &other).is_err());
+
+        // the unsigned tx
+        let unsigned = FullTransaction { v: None, ..tx.clone() };
+        match check_tx(unsigned, &receipt) {
+            Err(Error::InvalidEthTxSignature) => {}
+            other => panic!("unexpected result: {:?}", other.map(|(_, from)| from)),
+        }
+
+        // a call to the zero address is signed differently
+        let call = FullTransaction {
+            to: Some(H160::zero()),
+            ..tx
+        };
+        match check_tx(call.clone(), &receipt) {
+            Err(Error::InvalidEthTxSignature) => {}
+            other => panic!("unexpected result: {:?}", other.map(|(_, from)| from)),
+        }
+
+        // the reverted tx is never mapped
+        receipt.status = Some(0.into());
+        match check_tx(FullTransaction { to: None, ..call }, &receipt) {
+            Err(Error::EthTxFailed) => {}
+            other => panic!("unexpected result: {:?}", other.map(|(_, from)| from)),
+        }
+    }
+
+    #[test]
+    fn test_check_zero_input_tx() {
+        // the EIP-155 example, a plain transfer with empty data signed by the key 0x4646..46
+        let raw = hex!("f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83");
+        let tx = FullTransaction {
+            from: H160::from(hex!("9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f")),
+            to: Some(H160::repeat_byte(0x35)),
+            gas: 21000.into(),
+            gas_price: 20_000_000_000u64.into(),
+            hash: keccak(&raw[..]),
+            nonce: 9.into(),
+            r: Some(U256::from_big_endian(&hex!("28ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276"))),
+            s: Some(U256::from_big_endian(&hex!("67cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83"))),
+            v: Some(37.into()),
+            value: 1_000_000_000_000_000_000u64.into(),
+            ..Default::default()
+        };
+        match check_tx_sender(tx.clone()) {
+            Err(Error::EthTxInvalidData) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        let receipt = Receipt {
+            transaction_hash: tx.hash,
+            status: Some(1.into()),
+            ..Default::default()
+        };
+        match check_tx(tx, &receipt) {
+            Err(Error::EthTxInvalidData) => {}
+            other => panic!("unexpected result: {:?}", other.map(|(_, from)| from)),
+        }
+    }
+
+    #[test]
+    fn test_check_signed_message() {
+        let message = b"5UdrXD14mzNMnosk5PAYVTbWjFKrMwhjWuicLRGU3M8JcYBg@memo";
+        // personal_sign by the key 0x4646..46
+        let signature = hex!("7fc6c84dcae2e04cd08e093394cf62536349bd57218c0fb2ea63b7541ffaec9d09b47f1182308f6b113e732fb9071a46a2062e1aa9a9ef18abc6b904f24943031c");
+        let owner = H160::from(hex!("9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f"));
+
+        let (who, from) = check_signed_message(message, &signature, owner).unwrap();
+        assert_eq!(from, owner);
+        assert_eq!(who, check_tx_data(message).unwrap());
+
+        assert!(check_signed_message(message, &signature, H160::zero()).is_err());
+        assert!(check_signed_message(b"5UdrXD14mzNMnosk5PAYVTbWjFKrMwhjWuicLRGU3M8JcYBg", &signature, owner).is_err());
+        assert!(check_signed_message(message, &signature[..64], owner).is_err());
     }
 }
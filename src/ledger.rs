@@ -0,0 +1,252 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use substrate_primitives::crypto::Ss58Codec;
+use substrate_primitives::ed25519::Public;
+
+use chainx_primitives::{Balance, Index};
+
+use crate::error::{Error, Result};
+use crate::payout::Payout;
+use crate::types::{H160, H256, U256};
+use crate::verify::InclusionProof;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MappingStatus {
+    /// The mapping transaction has been accepted, but the SDOT has not been sent.
+    Pending,
+    /// The SDOT transfer has been signed with the nonce and submitted, but its inclusion is unknown.
+    ///
+    /// Saved before the submission, so that a retry never sends the SDOT again with another nonce.
+    Submitted { nonce: Index, extrinsic_hash: H256 },
+    /// The SDOT has been sent to the ChainX account.
+    Paid,
+}
+
+/// How the DOT owner proved the ownership of the Ethereum address.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MappingKind {
+    /// The mapping transaction, which has a block and an inclusion proof.
+    #[default]
+    Tx,
+    /// The message signed by `personal_sign`.
+    SignedMessage,
+    /// The EIP-712 claim signed by `eth_signTypedData`, the nonce cannot be used again by the address.
+    TypedClaim { nonce: U256 },
+}
+
+/// The record of an Ethereum address which has been mapped to a ChainX account.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MappingRecord {
+    pub from: H160,
+    /// The hash of the mapping transaction, or the signed hash of the message or the claim.
+    pub tx_hash: H256,
+    #[serde(default)]
+    pub kind: MappingKind,
+    /// The ChainX account in SS58 format.
+    pub who: String,
+    pub amount: Balance,
+    pub block_number: u64,
+    /// The hash of the block, used to detect the reorg before the final mapping.
+    #[serde(default)]
+    pub block_hash: Option<H256>,
+    /// The proof of the transaction in the block, so that the record can be audited offline.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proof: Option<InclusionProof>,
+    /// The proof of the receipt of the transaction, whose status and logs were checked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub receipt_proof: Option<InclusionProof>,
+    pub status: MappingStatus,
+}
+
+impl MappingRecord {
+    /// The pending record of the payout, without block.
+    pub fn new(kind: MappingKind, tx_hash: H256, payout: &Payout) -> Self {
+        Self {
+            from: payout.from,
+            tx_hash,
+            kind,
+            who: Public::from_slice(payout.who.as_ref()).to_ss58check(),
+            amount: payout.amount,
+            block_number: 0,
+            block_hash: None,
+            proof: None,
+            receipt_proof: None,
+            status: MappingStatus::Pending,
+        }
+    }
+
+    /// Re-check the inclusion proof of the transaction against the recorded block hash.
+    pub fn verify_proof(&self) -> Result<()> {
+        match (self.block_hash, self.proof.as_ref()) {
+            (Some(block_hash), Some(proof)) => proof.verify_tx(&block_hash, &self.tx_hash),
+            _ => {
+                warn!("Mapping record of {:?} has no inclusion proof", self.from);
+                Err(Error::InvalidEthProof)
+            }
+        }
+    }
+}
+
+/// File-backed ledger of the mapping records, keyed by Ethereum address and transaction hash.
+///
+/// The whole ledger is stored as a JSON array and rewritten on every change.
+pub struct Ledger {
+    path: PathBuf,
+    records: HashMap<H160, MappingRecord>,
+    tx_hashes: HashMap<H256, H160>,
+    claim_nonces: HashSet<(H160, U256)>,
+}
+
+impl Ledger {
+    /// Open the ledger file, an empty ledger is created if the file does not exist.
+    pub fn open<P: Into<PathBuf>>(path: P) -> Result<Self> {
+        let mut ledger = Self {
+            path: path.into(),
+            records: HashMap::new(),
+            tx_hashes: HashMap::new(),
+            claim_nonces: HashSet::new(),
+        };
+        if ledger.path.exists() {
+            let reader = BufReader::new(File::open(&ledger.path)?);
+            let records: Vec<MappingRecord> = serde_json::from_reader(reader)?;
+            for record in records {
+                ledger.insert(record);
+            }
+        }
+        Ok(ledger)
+    }
+
+    pub fn get(&self, from: &H160) -> Option<&MappingRecord> {
+        self.records.get(from)
+    }
+
+    #[allow(dead_code)]
+    pub fn get_by_tx(&self, hash: &H256) -> Option<&MappingRecord> {
+        self.tx_hashes.get(hash).and_then(|from| self.records.get(from))
+    }
+
+    /// Reject the address which has received the SDOT.
+    pub fn check_address(&self, from: &H160) -> Result<()> {
+        if self.records.contains_key(from) {
+            warn!("Ethereum address {:?} has been mapped", from);
+            return Err(Error::NoSdot);
+        }
+        Ok(())
+    }
+
+    /// Reject the address which has received the SDOT, or the transaction which has been used.
+    pub fn check(&self, from: &H160, hash: &H256) -> Result<()> {
+        if self.records.contains_key(from) || self.tx_hashes.contains_key(hash) {
+            warn!("Ethereum address {:?} or tx {:?} has been mapped", from, hash);
+            return Err(Error::NoSdot);
+        }
+        Ok(())
+    }
+
+    /// Reject the EIP-712 claim whose nonce has been used by the address.
+    pub fn check_claim(&self, from: &H160, nonce: U256) -> Result<()> {
+        if self.claim_nonces.contains(&(*from, nonce)) {
+            warn!("Nonce {} of the EIP-712 claim has been used by {:?}", nonce, from);
+            return Err(Error::NoSdot);
+        }
+        Ok(())
+    }
+
+    /// Add a new record and save the ledger.
+    pub fn record(&mut self, record: MappingRecord) -> Result<()> {
+        if let MappingKind::TypedClaim { nonce } = record.kind {
+            self.check_claim(&record.from, nonce)?;
+        }
+        self.check(&record.from, &record.tx_hash)?;
+        self.insert(record);
+        self.flush()
+    }
+
+    /// Update the status of the record and save the ledger.
+    pub fn set_status(&mut self, from: &H160, status: MappingStatus) -> Result<()> {
+        match self.records.get_mut(from) {
+            Some(record) => record.status = status,
+            None => return Err(Error::NoSdot),
+        }
+        self.flush()
+    }
+
+    /// The addresses whose SDOT has not been paid, in the order of the ledger file.
+    pub fn unpaid(&self) -> Vec<H160> {
+        let mut records = self.records.values().filter(|record| record.status != MappingStatus::Paid).collect::<Vec<_>>();
+        records.sort_by_key(|record| (record.block_number, record.tx_hash));
+        records.into_iter().map(|record| record.from).collect()
+    }
+
+    /// The first nonce of the payout account which no submitted payout has used.
+    pub fn next_payout_nonce(&self) -> Index {
+        let submitted = self.records.values().filter_map(|record| match record.status {
+            MappingStatus::Submitted { nonce, .. } => Some(nonce + 1),
+            _ => None,
+        });
+        submitted.max().unwrap_or(0)
+    }
+
+    fn insert(&mut self, record: MappingRecord) {
+        if let MappingKind::TypedClaim { nonce } = record.kind {
+            self.claim_nonces.insert((record.from, nonce));
+        }
+        self.tx_hashes.insert(record.tx_hash, record.from);
+        self.records.insert(record.from, record);
+    }
+
+    fn flush(&self) -> Result<()> {
+        let mut records = self.records.values().collect::<Vec<_>>();
+        records.sort_by_key(|record| (record.block_number, record.tx_hash));
+        // Write to a temporary file first, so that a crash never leaves a truncated ledger.
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(&records)?)?;
+        fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ledger_reject_duplicates() {
+        let path = std::env::temp_dir().join(format!("sdot-ledger-{}-reject-duplicates.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let record = MappingRecord {
+            from: H160::repeat_byte(1),
+            tx_hash: H256::repeat_byte(2),
+            kind: MappingKind::Tx,
+            who: "5UdrXD14mzNMnosk5PAYVTbWjFKrMwhjWuicLRGU3M8JcYBg".to_string(),
+            amount: 100,
+            block_number: 10,
+            block_hash: Some(H256::repeat_byte(5)),
+            proof: None,
+            receipt_proof: None,
+            status: MappingStatus::Pending,
+        };
+        // the record without proof cannot be audited
+        assert!(record.verify_proof().is_err());
+        let mut ledger = Ledger::open(path.clone()).unwrap();
+        ledger.check(&record.from, &record.tx_hash).unwrap();
+        ledger.record(record.clone()).unwrap();
+        ledger.set_status(&record.from, MappingStatus::Paid).unwrap();
+
+        let ledger = Ledger::open(path.clone()).unwrap();
+        assert_eq!(ledger.get(&record.from).unwrap().status, MappingStatus::Paid);
+        assert_eq!(ledger.get_by_tx(&record.tx_hash).unwrap().from, record.from);
+        // the same address with another tx
+        assert!(ledger.check(&record.from, &H256::repeat_byte(3)).is_err());
+        // the same tx replayed
+        assert!(ledger.check(&H160::repeat_byte(4), &record.tx_hash).is_err());
+        assert!(ledger.check(&H160::repeat_byte(4), &H256::repeat_byte(3)).is_ok());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -2,23 +2,48 @@
 
 #[macro_use]
 extern crate log;
-use std::fs::File;
+use std::fs::{self, File, OpenOptions};
 use std::io::prelude::*;
-use std::io::{self, BufReader};
+use std::io;
+use std::process;
+use std::time::Duration;
 
+mod batch;
+mod chainx;
 mod cli;
+mod confirm;
 mod decode;
+mod eip712;
+mod erc20;
 mod error;
 mod eth;
+mod ledger;
+mod mapping;
+mod payout;
+mod report;
+mod rpc;
+mod scanner;
+mod snapshot;
+mod source;
+mod trie;
 mod types;
+mod verify;
 
-use eth::{EtherScanApi, check_tx};
-use types::{H160, H256};
+use structopt::StructOpt;
 
-#[macro_use] extern crate hex_literal;
+use batch::BatchVerifier;
+use chainx::{HttpNode, Submitter};
+use cli::{Command, DecodeTxOpt, OutputOpt, PayOpt, ScanOpt, ServeOpt, SnapshotOpt, VerifyOpt};
+use error::{Error, Result};
+use eth::check_raw_tx;
+use ledger::Ledger;
+use mapping::{Mapping, MappingChecker};
+use report::{write_snapshot_issues, Entry, Outcome, Report};
+use scanner::Scanner;
+use snapshot::OwnerSnapshot;
+use verify::ReceiptCache;
 
 fn main() {
-    println!("Hello, world!");
     env_logger::Builder::new()
         .format(|buf, record| {
             let level = buf.default_styled_level(record.level());
@@ -33,30 +58,152 @@ fn main() {
         .filter_level(log::LevelFilter::Info)
         .init();
 
-    let fp_tx = File::open("tx-hash.txt").unwrap();
-    let f_tx = BufReader::new(fp_tx);
-
-
-    for line_hash in f_tx.lines() {
-        let fp_addr = File::open("eth-addr.txt").unwrap();
-        let f_eth = BufReader::new(fp_addr);
-        if let Ok(hash) = line_hash {
-            println!("hash: {:?}", hash);
-            let mut hex = hex::decode(hash).unwrap();
-            let result = EtherScanApi::new().get_tx_by_hash(H256::from_slice(&hex));
-
-            let (who, from) = check_tx(result.unwrap()).unwrap();
-            println!("who: {:?} from: {:?}", who, from);
-            for line in f_eth.lines() {
-                if let Ok(addr) = line {
-                    let addr = H160::from_slice(&hex::decode(addr).unwrap());
-                    if from == addr {
-                        println!("{:?}", "=================================");
-                        println!("from:{:?}", from);
-                        println!("addr:{:?}", addr);
-                    }
-                }
-            }
+    let conf = cli::init();
+    let cmd = conf.cmd.unwrap_or_else(|| Command::Serve(ServeOpt::from_iter(&["serve"])));
+    let result = match cmd {
+        Command::Serve(opt) => serve(&opt),
+        Command::Verify(opt) => verify(&opt),
+        Command::Scan(opt) => scan(&opt),
+        Command::DecodeTx(opt) => decode_tx(&opt),
+        Command::Snapshot(opt) => snapshot(&opt),
+        Command::Pay(opt) => pay(&opt),
+    };
+    // exit 1 if any entry of the report fails, 2 if the command itself fails
+    match result {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        Err(err) => {
+            error!("{}", err);
+            process::exit(2);
         }
     }
 }
+
+fn serve(opt: &ServeOpt) -> Result<bool> {
+    rpc::start_http(opt)?.wait();
+    Ok(true)
+}
+
+fn verify(opt: &VerifyOpt) -> Result<bool> {
+    let source = opt.source.open()?;
+    let mut ledger = Ledger::open(opt.check.ledger.clone())?;
+    let owners = OwnerSnapshot::load(&opt.owners)?;
+    let hashes = fs::read_to_string(&opt.hashes)?;
+    let mut verifier = BatchVerifier {
+        checker: MappingChecker {
+            source: &source,
+            owners: &owners,
+            calculator: &opt.payout.calculator(),
+            min_confirmations: opt.check.confirmations,
+            receipts: &ReceiptCache::default(),
+        },
+        ledger: &mut ledger,
+    };
+    let report = verifier.verify(&hashes);
+    report.write(opt.output.format, &mut output(&opt.output)?)?;
+    Ok(!report.has_failures())
+}
+
+fn scan(opt: &ScanOpt) -> Result<bool> {
+    let mut scanner = Scanner::new(opt.source.open()?, opt.deposit, opt.checkpoint.clone())
+        .with_confirmations(opt.check.confirmations);
+    if let Some(token) = opt.token {
+        scanner = scanner.with_token(token);
+    }
+    let mut ledger = Ledger::open(opt.check.ledger.clone())?;
+    let owners = OwnerSnapshot::load(&opt.owners)?;
+    let checker = MappingChecker {
+        source: scanner.source(),
+        owners: &owners,
+        calculator: &opt.payout.calculator(),
+        min_confirmations: opt.check.confirmations,
+        receipts: &ReceiptCache::default(),
+    };
+    let mut writer = match &opt.output {
+        Some(path) => Box::new(OpenOptions::new().create(true).append(true).open(path)?) as Box<dyn Write>,
+        None => Box::new(io::stdout()),
+    };
+    // The accepted mappings are recorded to the ledger, the rejected ones are reported with the reason.
+    // Each mapping is written before the checkpoint of its block is saved, any other failure stops
+    // the scan, so that a restart never skips a mapping.
+    let on_mapping = |mapping: Mapping| {
+        let hash = format!("{:?}", mapping.hash);
+        let entry = match checker.accept(&mut ledger, &mapping) {
+            Ok(amount) => Entry {
+                amount: Some(amount),
+                ..Entry::ok(hash, &mapping.who, mapping.from)
+            },
+            Err(err @ Error::NoSdot) | Err(err @ Error::NotDotOwner) | Err(err @ Error::InvalidPayout(_)) => {
+                Entry::failed(hash, Outcome::from_error(&err), err.to_string())
+            }
+            Err(err) => {
+                error!("Accept mapping tx {} of block #{} failed: {}", hash, mapping.block_number, err);
+                return Err(err);
+            }
+        };
+        write_json_line(&mut writer, &entry)
+    };
+    if opt.follow {
+        scanner.follow(opt.from, Duration::from_secs(opt.interval), on_mapping)?;
+        return Ok(true);
+    }
+    let to = match opt.to {
+        Some(to) => to,
+        None => scanner.confirmed_head()?,
+    };
+    scanner.scan_range(opt.from, to, on_mapping)?;
+    Ok(true)
+}
+
+fn decode_tx(opt: &DecodeTxOpt) -> Result<bool> {
+    let raw_tx = opt.raw_tx.trim();
+    let checked = hex::decode(raw_tx.trim_start_matches("0x"))
+        .map_err(Into::into)
+        .and_then(|raw_tx| check_raw_tx(&raw_tx));
+    let entry = match checked {
+        Ok((hash, who, from)) => Entry::ok(format!("{:?}", hash), &who, from),
+        Err(err) => Entry::failed(raw_tx.to_string(), Outcome::from_error(&err), err.to_string()),
+    };
+    let mut report = Report::default();
+    report.push(entry);
+    report.write(opt.output.format, &mut output(&opt.output)?)?;
+    Ok(!report.has_failures())
+}
+
+fn snapshot(opt: &SnapshotOpt) -> Result<bool> {
+    let (owners, issues) = OwnerSnapshot::load_lenient(&opt.owners)?;
+    write_snapshot_issues(owners.len(), &issues, opt.output.format, &mut output(&opt.output)?)?;
+    Ok(issues.is_empty())
+}
+
+fn pay(opt: &PayOpt) -> Result<bool> {
+    let source = opt.source.open()?;
+    let mut ledger = Ledger::open(opt.ledger.clone())?;
+    let mut submitter = Submitter::new(HttpNode::new(&opt.chainx_rpc)?, opt.signer()?, opt.call_index)?
+        .with_timeout(Duration::from_secs(opt.timeout), Duration::from_secs(2));
+    if let Some(nonce) = opt.nonce {
+        submitter = submitter.with_nonce(nonce);
+    }
+    let addresses = match opt.from {
+        Some(from) => vec![from],
+        None => ledger.unpaid(),
+    };
+    let report = submitter.pay_all(&source, &mut ledger, &addresses);
+    report.write(opt.output.format, &mut output(&opt.output)?)?;
+    Ok(!report.has_failures())
+}
+
+/// The report file of `-o`, or stdout.
+fn output(opt: &OutputOpt) -> Result<Box<dyn Write>> {
+    Ok(match &opt.output {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(io::stdout()),
+    })
+}
+
+fn write_json_line<W: Write>(writer: &mut W, entry: &Entry) -> Result<()> {
+    serde_json::to_writer(&mut *writer, entry)?;
+    writeln!(writer)?;
+    writer.flush()?;
+    Ok(())
+}
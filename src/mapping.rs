@@ -0,0 +1,177 @@
+use chainx_primitives::{AccountId, Balance};
+
+use crate::confirm::{check_confirmations, check_record};
+use crate::error::{Error, Result};
+use crate::eth::check_tx_with_ledger;
+use crate::ledger::{Ledger, MappingKind, MappingRecord};
+use crate::payout::PayoutCalculator;
+use crate::snapshot::OwnerSnapshot;
+use crate::source::TxSource;
+use crate::types::{FullTransaction, Receipt, H160, H256};
+use crate::verify::{verify_tx, verify_tx_receipt, ReceiptCache};
+
+/// A validated mapping transaction, found by the scanner or checked by `MappingChecker::check_hash`.
+#[derive(Debug, Clone)]
+pub struct Mapping {
+    pub who: AccountId,
+    pub from: H160,
+    pub hash: H256,
+    pub block_number: u64,
+    pub tx: FullTransaction,
+    pub receipt: Receipt,
+}
+
+/// Accept the mapping transactions: the confirmations, the block of the transaction and the SDOT payout.
+///
+/// Shared by the RPC, the batch verification and the scanner, so that every accepted mapping
+/// is turned into the same ledger record.
+pub struct MappingChecker<'a, S> {
+    pub source: &'a S,
+    pub owners: &'a OwnerSnapshot,
+    pub calculator: &'a PayoutCalculator,
+    pub min_confirmations: u64,
+    /// The receipts of the blocks verified by this checker, shared by the mappings of the run.
+    pub receipts: &'a ReceiptCache,
+}
+
+impl<'a, S: TxSource> MappingChecker<'a, S> {
+    /// Check the mapping transaction `hash`, and reject it if the address or the transaction has been mapped.
+    pub fn check_hash(&self, hash: H256, ledger: &Ledger) -> Result<Mapping> {
+        let tx = self.source.get_tx_by_hash(hash)?;
+        let receipt = self.source.get_receipt(hash)?;
+        let (who, from) = check_tx_with_ledger(tx.clone(), &receipt, ledger)?;
+        let block_number = match tx.block_number {
+            Some(number) => number.as_u64(),
+            None => return Err(Error::EthTxNotInBlock),
+        };
+        Ok(Mapping {
+            who,
+            from,
+            hash,
+            block_number,
+            tx,
+            receipt,
+        })
+    }
+
+    /// Build the record of the mapping checked by `check_tx` or `check_token_tx`,
+    /// the record is not saved to the ledger.
+    ///
+    /// The receipt, whose status and logs were checked, is verified against the block as well as the transaction.
+    pub fn record(&self, mapping: &Mapping) -> Result<MappingRecord> {
+        let tx = &mapping.tx;
+        check_confirmations(self.source, tx, self.min_confirmations)?;
+        let proof = verify_tx(self.source, tx)?;
+        let receipt_proof = verify_tx_receipt(self.source, self.receipts, tx, &mapping.receipt)?;
+        let payout = self.calculator.payout(self.owners, mapping.who, mapping.from)?;
+        let record = MappingRecord {
+            block_number: mapping.block_number,
+            block_hash: tx.block_hash,
+            proof: Some(proof),
+            receipt_proof: Some(receipt_proof),
+            ..MappingRecord::new(MappingKind::Tx, tx.hash, &payout)
+        };
+        // The proof is checked against the recorded block hash, as the auditors will do,
+        // and the block must still be on the canonical chain when the record is saved.
+        record.verify_proof()?;
+        check_record(self.source, &record)?;
+        Ok(record)
+    }
+
+    /// Build the record of the mapping and save it to the ledger, return the SDOT amount.
+    pub fn accept(&self, ledger: &mut Ledger, mapping: &Mapping) -> Result<Balance> {
+        let record = self.record(mapping)?;
+        let amount = record.amount;
+        ledger.record(record)?;
+        Ok(amount)
+    }
+
+    /// Build the record of the mapping signed by the DOT owner without transaction,
+    /// `hash` is the signed hash of the message or the claim.
+    pub fn record_signed(&self, kind: MappingKind, hash: H256, who: AccountId, from: H160) -> Result<MappingRecord> {
+        let payout = self.calculator.payout(self.owners, who, from)?;
+        Ok(MappingRecord::new(kind, hash, &payout))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eth::check_tx;
+    use crate::payout::Ratio;
+    use crate::snapshot::SnapshotFormat;
+    use crate::source::FixtureSource;
+    use crate::types::{FullBlock, Receipt};
+    use std::cell::Cell;
+
+    /// The fixture chain whose block #1 is reorganized after it has been fetched `reorg_after` times.
+    struct Reorged {
+        fixtures: FixtureSource,
+        reorg_after: u32,
+        fetched: Cell<u32>,
+    }
+
+    impl TxSource for Reorged {
+        fn get_block_number(&self) -> Result<u64> {
+            self.fixtures.get_block_number()
+        }
+
+        fn get_tx_by_hash(&self, hash: H256) -> Result<FullTransaction> {
+            self.fixtures.get_tx_by_hash(hash)
+        }
+
+        fn get_block_by_number(&self, number: u64) -> Result<FullBlock> {
+            let mut block = self.fixtures.get_block_by_number(number)?;
+            self.fetched.set(self.fetched.get() + 1);
+            if number == 1 && self.fetched.get() > self.reorg_after {
+                block.hash = Some(H256::repeat_byte(1));
+            }
+            Ok(block)
+        }
+
+        fn get_receipt(&self, hash: H256) -> Result<Receipt> {
+            self.fixtures.get_receipt(hash)
+        }
+    }
+
+    #[test]
+    fn test_record_reorged_tx() {
+        let (owners, _) =
+            OwnerSnapshot::parse("9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f,1500\n", SnapshotFormat::Csv).unwrap();
+        let calculator = PayoutCalculator::new(Ratio::new(1, 1), 3, 3);
+        let hash = "ece0a27a08d6a1765d2331e596a42da43bbb1acb2293fc818d0259a56b0a0166".parse().unwrap();
+        let check = |reorg_after| {
+            let source = Reorged {
+                fixtures: FixtureSource::new(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures")),
+                reorg_after,
+                fetched: Cell::new(0),
+            };
+            let checker = MappingChecker {
+                source: &source,
+                owners: &owners,
+                calculator: &calculator,
+                min_confirmations: 1,
+                receipts: &ReceiptCache::default(),
+            };
+            let tx = source.get_tx_by_hash(hash).unwrap();
+            let receipt = source.get_receipt(hash).unwrap();
+            let (who, from) = check_tx(tx.clone(), &receipt).unwrap();
+            checker.record(&Mapping {
+                who,
+                from,
+                hash,
+                block_number: 1,
+                tx,
+                receipt,
+            })
+        };
+
+        let record = check(u32::MAX).unwrap();
+        assert_eq!((record.block_number, record.amount), (1, 1500));
+        // the block is reorganized after the inclusion of the tx and the receipt is verified
+        match check(2) {
+            Err(Error::EthChainReorg) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chainx_primitives::{AccountId, Balance};
+
+use crate::error::{Error, Result};
+use crate::snapshot::{DotAmount, OwnerSnapshot};
+use crate::types::H160;
+
+/// The DOT→SDOT conversion ratio `numerator / denominator`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Ratio {
+    pub numerator: u128,
+    pub denominator: u128,
+}
+
+impl Ratio {
+    pub fn new(numerator: u128, denominator: u128) -> Self {
+        Self { numerator, denominator }
+    }
+}
+
+impl fmt::Display for Ratio {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.numerator, self.denominator)
+    }
+}
+
+/// Parse the ratio as a fraction `3/2` or a decimal `1.5`.
+impl FromStr for Ratio {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, String> {
+        let s = s.trim();
+        let invalid = || format!("invalid ratio '{}'", s);
+        let ratio = if let Some(index) = s.find('/') {
+            let numerator = s[..index].trim().parse().map_err(|_| invalid())?;
+            let denominator = s[index + 1..].trim().parse().map_err(|_| invalid())?;
+            Ratio::new(numerator, denominator)
+        } else {
+            let (integer, fraction) = match s.find('.') {
+                Some(index) => (&s[..index], &s[index + 1..]),
+                None => (s, ""),
+            };
+            if integer.is_empty() || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(invalid());
+            }
+            let numerator = format!("{}{}", integer, fraction).parse().map_err(|_| invalid())?;
+            let denominator = 10u128.checked_pow(fraction.len() as u32).ok_or_else(invalid)?;
+            Ratio::new(numerator, denominator)
+        };
+        if ratio.denominator == 0 {
+            return Err(invalid());
+        }
+        Ok(ratio)
+    }
+}
+
+/// How the fraction of the smallest SDOT unit is handled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    /// Truncate the fraction.
+    Down,
+    /// Round the fraction up to the next unit.
+    Up,
+    /// Round to the nearest unit, the half is rounded up.
+    Nearest,
+}
+
+impl FromStr for Rounding {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, String> {
+        match s {
+            "down" => Ok(Rounding::Down),
+            "up" => Ok(Rounding::Up),
+            "nearest" => Ok(Rounding::Nearest),
+            _ => Err(format!("invalid rounding '{}', expected down, up or nearest", s)),
+        }
+    }
+}
+
+/// The SDOT payout of a mapping.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Payout {
+    pub who: AccountId,
+    pub from: H160,
+    /// The DOT amount of the owner in the snapshot.
+    pub dot: DotAmount,
+    /// The SDOT amount, in the smallest unit of SDOT.
+    pub amount: Balance,
+}
+
+/// Compute the SDOT amount from the DOT amount of the owner snapshot:
+/// `dot * ratio * 10^sdot_decimals / 10^dot_decimals`, rounded by the rounding mode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PayoutCalculator {
+    ratio: Ratio,
+    dot_decimals: u32,
+    sdot_decimals: u32,
+    rounding: Rounding,
+}
+
+impl PayoutCalculator {
+    /// `dot_decimals` is the decimals of the snapshot balance, `sdot_decimals` is the decimals of SDOT on ChainX.
+    pub fn new(ratio: Ratio, dot_decimals: u32, sdot_decimals: u32) -> Self {
+        Self {
+            ratio,
+            dot_decimals,
+            sdot_decimals,
+            rounding: Rounding::Down,
+        }
+    }
+
+    pub fn with_rounding(mut self, rounding: Rounding) -> Self {
+        self.rounding = rounding;
+        self
+    }
+
+    /// The SDOT amount of the DOT amount.
+    pub fn amount(&self, dot: DotAmount) -> Result<Balance> {
+        let overflow = || Error::InvalidPayout(format!("{} DOT overflows with ratio {}", dot, self.ratio));
+        let numerator = 10u128
+            .checked_pow(self.sdot_decimals)
+            .and_then(|scale| dot.checked_mul(self.ratio.numerator)?.checked_mul(scale))
+            .ok_or_else(overflow)?;
+        let denominator = 10u128
+            .checked_pow(self.dot_decimals)
+            .and_then(|scale| self.ratio.denominator.checked_mul(scale))
+            .ok_or_else(overflow)?;
+        let (quotient, remainder) = (numerator / denominator, numerator % denominator);
+        let amount = match self.rounding {
+            Rounding::Down => quotient,
+            Rounding::Up if remainder > 0 => quotient + 1,
+            Rounding::Up => quotient,
+            // `remainder >= denominator - remainder` is `remainder / denominator >= 1/2` without overflow
+            Rounding::Nearest if remainder >= denominator - remainder => quotient + 1,
+            Rounding::Nearest => quotient,
+        };
+        if amount > u128::from(Balance::MAX) {
+            return Err(overflow());
+        }
+        Ok(amount as Balance)
+    }
+
+    /// The payout of the mapping `(who, from)` checked by `check_tx`, `from` must be an owner with balance.
+    pub fn payout(&self, owners: &OwnerSnapshot, who: AccountId, from: H160) -> Result<Payout> {
+        if !owners.is_owner(&from) {
+            warn!("Ethereum address {:?} is not a DOT owner", from);
+            return Err(Error::NotDotOwner);
+        }
+        let dot = match owners.balance(&from) {
+            Some(dot) => dot,
+            None => return Err(Error::InvalidPayout(format!("no DOT balance of {:?} in the snapshot", from))),
+        };
+        let amount = self.amount(dot)?;
+        if amount == 0 {
+            return Err(Error::InvalidPayout(format!("{} DOT of {:?} is less than 1 SDOT unit", dot, from)));
+        }
+        Ok(Payout { who, from, dot, amount })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot::SnapshotFormat;
+
+    #[test]
+    fn test_parse_ratio() {
+        assert_eq!("3/2".parse::<Ratio>().unwrap(), Ratio::new(3, 2));
+        assert_eq!("1.5".parse::<Ratio>().unwrap(), Ratio::new(15, 10));
+        assert_eq!("100".parse::<Ratio>().unwrap(), Ratio::new(100, 1));
+        assert!("1/0".parse::<Ratio>().is_err());
+        assert!(".5".parse::<Ratio>().is_err());
+        assert!("1.-5".parse::<Ratio>().is_err());
+        assert!("one".parse::<Ratio>().is_err());
+    }
+
+    #[test]
+    fn test_amount() {
+        // 1 DOT (10 decimals) => 100 SDOT (3 decimals)
+        let calculator = PayoutCalculator::new(Ratio::new(100, 1), 10, 3);
+        assert_eq!(calculator.amount(10_000_000_000).unwrap(), 100_000);
+        assert_eq!(calculator.amount(1_234_567).unwrap(), 12);
+
+        let ratio = Ratio::new(1, 3);
+        let down = PayoutCalculator::new(ratio, 0, 0);
+        assert_eq!(down.amount(5).unwrap(), 1);
+        assert_eq!(down.with_rounding(Rounding::Up).amount(5).unwrap(), 2);
+        assert_eq!(down.with_rounding(Rounding::Up).amount(6).unwrap(), 2);
+        assert_eq!(down.with_rounding(Rounding::Nearest).amount(4).unwrap(), 1);
+        assert_eq!(down.with_rounding(Rounding::Nearest).amount(5).unwrap(), 2);
+        // the half is rounded up
+        let half = PayoutCalculator::new(Ratio::new(1, 2), 0, 0).with_rounding(Rounding::Nearest);
+        assert_eq!(half.amount(3).unwrap(), 2);
+
+        let overflow = PayoutCalculator::new(Ratio::new(1, 1), 0, 0);
+        assert!(overflow.amount(u128::from(Balance::MAX) + 1).is_err());
+        assert!(PayoutCalculator::new(Ratio::new(1, 1), 0, 40).amount(1).is_err());
+    }
+
+    #[test]
+    fn test_payout() {
+        let alice = H160::repeat_byte(1);
+        let bob = H160::repeat_byte(2);
+        let content = format!("{:x},20000\n{:x},1\n", alice, bob);
+        let (owners, _) = OwnerSnapshot::parse(&content, SnapshotFormat::Csv).unwrap();
+        let who = AccountId::from_slice(&[0x11; 32]);
+        let calculator = PayoutCalculator::new(Ratio::new(1, 2), 3, 3);
+
+        let payout = calculator.payout(&owners, who, alice).unwrap();
+        assert_eq!((payout.dot, payout.amount), (20_000, 10_000));
+        match calculator.payout(&owners, who, H160::repeat_byte(3)) {
+            Err(Error::NotDotOwner) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match calculator.payout(&owners, who, bob) {
+            Err(Error::InvalidPayout(_)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
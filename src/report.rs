@@ -0,0 +1,344 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+
+use serde::Serialize;
+use substrate_primitives::crypto::Ss58Codec;
+use substrate_primitives::ed25519::Public;
+
+use chainx_primitives::{AccountId, Balance};
+
+use crate::error::{Error, Result};
+use crate::snapshot::SnapshotIssue;
+use crate::types::H160;
+
+/// The outcome of verifying a mapping transaction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Ok,
+    NotFound,
+    BadSignature,
+    InvalidData,
+    NotOwner,
+    Duplicate,
+    /// Any other failure, e.g. the pending confirmations or the network error.
+    Error,
+}
+
+impl Outcome {
+    pub const ALL: [Outcome; 7] = [
+        Outcome::Ok,
+        Outcome::NotFound,
+        Outcome::BadSignature,
+        Outcome::InvalidData,
+        Outcome::NotOwner,
+        Outcome::Duplicate,
+        Outcome::Error,
+    ];
+
+    pub fn from_error(err: &Error) -> Self {
+        match err {
+            Error::EtherScanCannotGetTx | Error::NonExistentEthTx | Error::NonExistentEthBlock => Outcome::NotFound,
+            Error::InvalidEthTxSignature | Error::InvalidEthMessageSignature => Outcome::BadSignature,
+            Error::EthTxInvalidData | Error::InvalidTokenTransfer | Error::Hex(_) => Outcome::InvalidData,
+            Error::NotDotOwner => Outcome::NotOwner,
+            Error::NoSdot => Outcome::Duplicate,
+            _ => Outcome::Error,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Outcome::Ok => "ok",
+            Outcome::NotFound => "not_found",
+            Outcome::BadSignature => "bad_signature",
+            Outcome::InvalidData => "invalid_data",
+            Outcome::NotOwner => "not_owner",
+            Outcome::Duplicate => "duplicate",
+            Outcome::Error => "error",
+        }
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The result of one line of the hash file.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Entry {
+    /// The transaction hash as written in the hash file.
+    pub hash: String,
+    pub outcome: Outcome,
+    /// The ChainX account in SS58 format.
+    pub who: Option<String>,
+    pub from: Option<H160>,
+    /// The SDOT payout amount.
+    pub amount: Option<Balance>,
+    /// The error message of the failure.
+    pub message: Option<String>,
+}
+
+impl Entry {
+    pub fn ok(hash: String, who: &AccountId, from: H160) -> Self {
+        Self {
+            hash,
+            outcome: Outcome::Ok,
+            who: Some(Public::from_slice(who.as_ref()).to_ss58check()),
+            from: Some(from),
+            amount: None,
+            message: None,
+        }
+    }
+
+    pub fn failed(hash: String, outcome: Outcome, message: String) -> Self {
+        Self {
+            hash,
+            outcome,
+            who: None,
+            from: None,
+            amount: None,
+            message: Some(message),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReportFormat {
+    Json,
+    Csv,
+    Table,
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, String> {
+        match s {
+            "json" => Ok(ReportFormat::Json),
+            "csv" => Ok(ReportFormat::Csv),
+            "table" => Ok(ReportFormat::Table),
+            _ => Err(format!("invalid report format '{}', expected json, csv or table", s)),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    total: usize,
+    summary: BTreeMap<&'static str, usize>,
+    entries: &'a [Entry],
+}
+
+/// The report of the batch verification.
+#[derive(Clone, Debug, Default)]
+pub struct Report {
+    pub entries: Vec<Entry>,
+}
+
+impl Report {
+    pub fn push(&mut self, entry: Entry) {
+        self.entries.push(entry);
+    }
+
+    pub fn count(&self, outcome: Outcome) -> usize {
+        self.entries.iter().filter(|entry| entry.outcome == outcome).count()
+    }
+
+    /// The number of entries of each outcome, the outcomes without entry are omitted.
+    pub fn summary(&self) -> Vec<(Outcome, usize)> {
+        Outcome::ALL
+            .iter()
+            .map(|outcome| (*outcome, self.count(*outcome)))
+            .filter(|(_, count)| *count > 0)
+            .collect()
+    }
+
+    pub fn has_failures(&self) -> bool {
+        self.entries.iter().any(|entry| entry.outcome != Outcome::Ok)
+    }
+
+    pub fn write<W: Write>(&self, format: ReportFormat, writer: &mut W) -> Result<()> {
+        match format {
+            ReportFormat::Json => {
+                let report = JsonReport {
+                    total: self.entries.len(),
+                    summary: self.summary().into_iter().map(|(outcome, count)| (outcome.as_str(), count)).collect(),
+                    entries: &self.entries,
+                };
+                serde_json::to_writer_pretty(&mut *writer, &report)?;
+                writeln!(writer)?;
+            }
+            ReportFormat::Csv => {
+                writeln!(writer, "hash,outcome,who,from,amount,message")?;
+                for entry in &self.entries {
+                    let row = Self::columns(entry).iter().map(|column| csv_field(column)).collect::<Vec<_>>();
+                    writeln!(writer, "{}", row.join(","))?;
+                }
+            }
+            ReportFormat::Table => self.write_table(writer)?,
+        }
+        Ok(())
+    }
+
+    fn columns(entry: &Entry) -> [String; 6] {
+        [
+            entry.hash.clone(),
+            entry.outcome.to_string(),
+            entry.who.clone().unwrap_or_default(),
+            entry.from.map(|from| format!("{:?}", from)).unwrap_or_default(),
+            entry.amount.map(|amount| amount.to_string()).unwrap_or_default(),
+            entry.message.clone().unwrap_or_default(),
+        ]
+    }
+
+    fn write_table<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let header = ["HASH", "OUTCOME", "WHO", "FROM", "AMOUNT", "MESSAGE"];
+        let rows = self.entries.iter().map(Self::columns).collect::<Vec<_>>();
+        let mut widths = header.iter().map(|column| column.len()).collect::<Vec<_>>();
+        for row in &rows {
+            for (width, column) in widths.iter_mut().zip(row.iter()) {
+                *width = (*width).max(column.chars().count());
+            }
+        }
+        let line = |columns: Vec<&str>| {
+            let padded = columns
+                .iter()
+                .zip(widths.iter())
+                .map(|(column, width)| format!("{:<width$}", column, width = width))
+                .collect::<Vec<_>>();
+            padded.join("  ").trim_end().to_string()
+        };
+        writeln!(writer, "{}", line(header.to_vec()))?;
+        for row in &rows {
+            writeln!(writer, "{}", line(row.iter().map(String::as_str).collect()))?;
+        }
+        let summary = self
+            .summary()
+            .iter()
+            .map(|(outcome, count)| format!("{}: {}", outcome, count))
+            .collect::<Vec<_>>();
+        writeln!(writer, "\ntotal: {}, {}", self.entries.len(), summary.join(", "))?;
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+struct JsonSnapshotReport<'a> {
+    owners: usize,
+    issues: &'a [SnapshotIssue],
+}
+
+/// Write the duplicate and malformed entries of the owner snapshot of `owners` valid owners.
+pub fn write_snapshot_issues<W: Write>(
+    owners: usize,
+    issues: &[SnapshotIssue],
+    format: ReportFormat,
+    writer: &mut W,
+) -> Result<()> {
+    match format {
+        ReportFormat::Json => {
+            serde_json::to_writer_pretty(&mut *writer, &JsonSnapshotReport { owners, issues })?;
+            writeln!(writer)?;
+        }
+        ReportFormat::Csv => {
+            writeln!(writer, "line,reason")?;
+            for issue in issues {
+                writeln!(writer, "{},{}", issue.line, csv_field(&issue.reason))?;
+            }
+        }
+        ReportFormat::Table => {
+            for issue in issues {
+                writeln!(writer, "{}", issue)?;
+            }
+            writeln!(writer, "owners: {}, issues: {}", owners, issues.len())?;
+        }
+    }
+    Ok(())
+}
+
+/// Quote the CSV field if it contains the separator, the quote or the line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> Report {
+        let mut report = Report::default();
+        let who = AccountId::from_slice(&[0x11; 32]);
+        report.push(Entry {
+            amount: Some(1000),
+            ..Entry::ok("0x01".to_string(), &who, H160::repeat_byte(1))
+        });
+        report.push(Entry::failed(
+            "0x02".to_string(),
+            Outcome::from_error(&Error::InvalidEthTxSignature),
+            "bad, \"signature\"".to_string(),
+        ));
+        report
+    }
+
+    #[test]
+    fn test_report_summary() {
+        let mut report = report();
+        assert!(report.has_failures());
+        assert_eq!(report.summary(), vec![(Outcome::Ok, 1), (Outcome::BadSignature, 1)]);
+        report.entries.pop();
+        assert!(!report.has_failures());
+        assert_eq!(Outcome::from_error(&Error::InvalidEthMessageSignature), Outcome::BadSignature);
+        assert_eq!(Outcome::from_error(&Error::NoSdot), Outcome::Duplicate);
+        assert_eq!(Outcome::from_error(&Error::NotDotOwner), Outcome::NotOwner);
+        assert_eq!(Outcome::from_error(&Error::PendingConfirmations(1, 12)), Outcome::Error);
+    }
+
+    #[test]
+    fn test_write_report() {
+        let report = report();
+        let mut csv = vec![];
+        report.write(ReportFormat::Csv, &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv.lines().nth(2).unwrap(), r#"0x02,bad_signature,,,,"bad, ""signature""""#);
+
+        let mut json = vec![];
+        report.write(ReportFormat::Json, &mut json).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(json["entries"][0]["outcome"], "ok");
+        assert_eq!(json["entries"][0]["amount"], 1000);
+        assert_eq!(json["total"], 2);
+        assert_eq!(json["summary"]["bad_signature"], 1);
+
+        let mut table = vec![];
+        report.write(ReportFormat::Table, &mut table).unwrap();
+        let table = String::from_utf8(table).unwrap();
+        assert!(table.starts_with("HASH  OUTCOME"));
+        assert!(table.ends_with("total: 2, ok: 1, bad_signature: 1\n"));
+    }
+
+    #[test]
+    fn test_write_snapshot_issues() {
+        let issues = vec![SnapshotIssue {
+            line: 3,
+            reason: "invalid balance 'x,y'".to_string(),
+        }];
+        let mut csv = vec![];
+        write_snapshot_issues(2, &issues, ReportFormat::Csv, &mut csv).unwrap();
+        assert_eq!(String::from_utf8(csv).unwrap(), "line,reason\n3,\"invalid balance 'x,y'\"\n");
+
+        let mut json = vec![];
+        write_snapshot_issues(2, &issues, ReportFormat::Json, &mut json).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(json["owners"], 2);
+        assert_eq!(json["issues"][0]["line"], 3);
+    }
+}
@@ -0,0 +1,380 @@
+use std::sync::{Arc, RwLock};
+
+use jsonrpc_core::{IoHandler, Result as RpcResult};
+use jsonrpc_derive::rpc;
+use jsonrpc_http_server::{Server, ServerBuilder};
+use serde::{Deserialize, Serialize};
+
+use chainx_primitives::Balance;
+
+use crate::cli::{config_url, ServeOpt};
+use crate::eip712::{check_typed_claim, Claim, Eip712Domain};
+use crate::error::{Error, Result};
+use crate::eth::{check_raw_tx, check_signed_message, personal_message_hash};
+use crate::ledger::{Ledger, MappingKind, MappingRecord};
+use crate::mapping::MappingChecker;
+use crate::payout::PayoutCalculator;
+use crate::snapshot::OwnerSnapshot;
+use crate::source::TxSource;
+use crate::types::{Bytes, H160, H256, U256};
+use crate::verify::ReceiptCache;
+
+/// The ChainX account, Ethereum sender and SDOT payout of a valid mapping transaction.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MappingResult {
+    /// ChainX account (SS58 format) decoded from the transaction data field.
+    pub who: String,
+    /// Ethereum address that signed the transaction.
+    pub from: H160,
+    /// The SDOT amount, in the smallest unit of SDOT.
+    pub amount: Balance,
+}
+
+impl From<&MappingRecord> for MappingResult {
+    fn from(record: &MappingRecord) -> Self {
+        Self {
+            who: record.who.clone(),
+            from: record.from,
+            amount: record.amount,
+        }
+    }
+}
+
+#[rpc]
+pub trait SdotApi {
+    /// Check the Ethereum transaction and return the ChainX account it maps to, with the SDOT amount.
+    /// The ledger is not changed, so the check can be repeated until the mapping is recorded by `sdot_mapTx`.
+    #[rpc(name = "sdot_checkTx")]
+    fn check_tx(&self, hash: H256) -> RpcResult<MappingResult>;
+
+    /// Check the raw signed Ethereum transaction as `sdot_checkTx` of its hash.
+    #[rpc(name = "sdot_checkRawTx")]
+    fn check_raw_tx(&self, raw: Bytes) -> RpcResult<MappingResult>;
+
+    /// Check the Ethereum transaction as `sdot_checkTx`, and record the mapping to the ledger to be paid.
+    #[rpc(name = "sdot_mapTx")]
+    fn map_tx(&self, hash: H256) -> RpcResult<MappingResult>;
+
+    /// Check the message signed by `personal_sign` and return the ChainX account it maps to.
+    /// The accepted mapping is recorded to the ledger, as there is no transaction to check again.
+    #[rpc(name = "sdot_checkSignedMessage")]
+    fn check_signed_message(&self, message: String, signature: Bytes, address: H160) -> RpcResult<MappingResult>;
+
+    /// Check the EIP-712 claim signed by `eth_signTypedData` and return the ChainX account it maps to.
+    /// The accepted mapping is recorded to the ledger as `sdot_checkSignedMessage`.
+    #[rpc(name = "sdot_checkTypedClaim")]
+    fn check_typed_claim(
+        &self,
+        domain: Eip712Domain,
+        claim: Claim,
+        signature: Bytes,
+        address: H160,
+    ) -> RpcResult<MappingResult>;
+}
+
+pub struct SdotRpc {
+    api: Box<dyn TxSource + Send + Sync>,
+    ledger: Arc<RwLock<Ledger>>,
+    owners: OwnerSnapshot,
+    calculator: PayoutCalculator,
+    /// The domain of the EIP-712 claims, `None` if they are not accepted.
+    domain: Option<Eip712Domain>,
+    min_confirmations: u64,
+}
+
+impl SdotRpc {
+    pub fn new(
+        api: Box<dyn TxSource + Send + Sync>,
+        ledger: Arc<RwLock<Ledger>>,
+        owners: OwnerSnapshot,
+        calculator: PayoutCalculator,
+        domain: Option<Eip712Domain>,
+        min_confirmations: u64,
+    ) -> Self {
+        Self {
+            api,
+            ledger,
+            owners,
+            calculator,
+            domain,
+            min_confirmations,
+        }
+    }
+
+    /// The checker of one request, the receipts are cached by a batch or a scan run only.
+    fn checker<'a>(&'a self, receipts: &'a ReceiptCache) -> MappingChecker<'a, Box<dyn TxSource + Send + Sync>> {
+        MappingChecker {
+            source: &self.api,
+            owners: &self.owners,
+            calculator: &self.calculator,
+            min_confirmations: self.min_confirmations,
+            receipts,
+        }
+    }
+
+    /// Build the record of the mapping transaction, the ledger is only read.
+    fn check_record(&self, hash: H256) -> Result<MappingRecord> {
+        let receipts = ReceiptCache::default();
+        let checker = self.checker(&receipts);
+        let mapping = checker.check_hash(hash, &self.ledger.read().expect("Ledger lock poisoned"))?;
+        checker.record(&mapping)
+    }
+
+    /// Save the accepted mapping. The ledger is checked again under the write lock,
+    /// so that the concurrent requests cannot map the same address or transaction twice.
+    fn record(&self, record: MappingRecord) -> Result<MappingResult> {
+        let result = MappingResult::from(&record);
+        self.ledger.write().expect("Ledger lock poisoned").record(record)?;
+        Ok(result)
+    }
+}
+
+impl SdotApi for SdotRpc {
+    fn check_tx(&self, hash: H256) -> RpcResult<MappingResult> {
+        let record = self.check_record(hash)?;
+        Ok(MappingResult::from(&record))
+    }
+
+    fn check_raw_tx(&self, raw: Bytes) -> RpcResult<MappingResult> {
+        // The signature only proves the tx was signed, it must have been mined and succeeded as well.
+        let (hash, _, _) = check_raw_tx(&raw.0)?;
+        self.check_tx(hash)
+    }
+
+    fn map_tx(&self, hash: H256) -> RpcResult<MappingResult> {
+        let record = self.check_record(hash)?;
+        Ok(self.record(record)?)
+    }
+
+    fn check_signed_message(&self, message: String, signature: Bytes, address: H160) -> RpcResult<MappingResult> {
+        let (who, from) = check_signed_message(message.as_bytes(), &signature.0, address)?;
+        self.ledger.read().expect("Ledger lock poisoned").check_address(&from)?;
+        let hash = personal_message_hash(message.as_bytes());
+        let record = self.checker(&ReceiptCache::default()).record_signed(MappingKind::SignedMessage, hash, who, from)?;
+        Ok(self.record(record)?)
+    }
+
+    fn check_typed_claim(
+        &self,
+        domain: Eip712Domain,
+        claim: Claim,
+        signature: Bytes,
+        address: H160,
+    ) -> RpcResult<MappingResult> {
+        let expected = match self.domain {
+            Some(ref expected) => expected,
+            None => {
+                warn!("EIP-712 claim of {:?} is rejected without the claim contract", address);
+                return Err(Error::InvalidClaimDomain.into());
+            }
+        };
+        let (who, from) = check_typed_claim(expected, &domain, &claim, &signature.0, address)?;
+        {
+            let ledger = self.ledger.read().expect("Ledger lock poisoned");
+            ledger.check_claim(&from, claim.nonce)?;
+            ledger.check_address(&from)?;
+        }
+        let hash = claim.signing_hash(expected);
+        let kind = MappingKind::TypedClaim { nonce: claim.nonce };
+        let record = self.checker(&ReceiptCache::default()).record_signed(kind, hash, who, from)?;
+        // The owner signs the amount, which must be the payout of the snapshot.
+        if claim.amount != U256::from(record.amount) {
+            warn!("EIP-712 claim of {:?} has amount {}, the payout is {}", from, claim.amount, record.amount);
+            let msg = format!("claimed {} SDOT, the payout is {}", claim.amount, record.amount);
+            return Err(Error::InvalidPayout(msg).into());
+        }
+        Ok(self.record(record)?)
+    }
+}
+
+pub fn start_http(opt: &ServeOpt) -> Result<Server> {
+    let api = opt.source.open()?;
+    let ledger = Arc::new(RwLock::new(Ledger::open(opt.check.ledger.clone())?));
+    let mut io = IoHandler::new();
+    let owners = OwnerSnapshot::load(&opt.owners)?;
+    let calculator = opt.payout.calculator();
+    let domain = opt.claim_contract.map(|contract| Eip712Domain::sdot(opt.claim_chain_id, contract));
+    io.extend_with(SdotRpc::new(api, ledger, owners, calculator, domain, opt.check.confirmations).to_delegate());
+
+    let addr: std::net::SocketAddr = config_url(opt).parse()?;
+    let server = ServerBuilder::new(io).threads(4).start_http(&addr)?;
+    info!("SDOT RPC server is listening on {}", addr);
+    Ok(server)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ledger::MappingStatus;
+    use crate::payout::Ratio;
+    use crate::snapshot::SnapshotFormat;
+    use crate::source::FixtureSource;
+    use crate::types::UnverifiedTransaction;
+    use std::fs;
+    use std::path::PathBuf;
+    use structopt::StructOpt;
+
+    // The mapping txs of the fixture blocks #1 and #2, sent by alice and bob
+    const ALICE_TX: &str = "ece0a27a08d6a1765d2331e596a42da43bbb1acb2293fc818d0259a56b0a0166";
+    const BOB_TX: &str = "a29417602258a521a722afdaaefc21a4b184ba76a3964c5f2850645dfbe56102";
+
+    fn rpc(name: &str) -> (SdotRpc, PathBuf) {
+        let path = std::env::temp_dir().join(format!("sdot-ledger-{}-rpc-{}.json", std::process::id(), name));
+        let _ = fs::remove_file(&path);
+        let source = FixtureSource::new(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures"));
+        let (owners, _) =
+            OwnerSnapshot::parse("9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f,1500\n", SnapshotFormat::Csv).unwrap();
+        let rpc = SdotRpc::new(
+            Box::new(source),
+            Arc::new(RwLock::new(Ledger::open(path.clone()).unwrap())),
+            owners,
+            PayoutCalculator::new(Ratio::new(2, 1), 3, 3),
+            Some(Eip712Domain::sdot(1, H160::repeat_byte(0x42))),
+            1,
+        );
+        (rpc, path)
+    }
+
+    #[test]
+    fn test_check_tx() {
+        let (rpc, path) = rpc("check-tx");
+        let result = rpc.check_tx(ALICE_TX.parse().unwrap()).unwrap();
+        assert_eq!(result.who, "5UdrXD14mzNMnosk5PAYVTbWjFKrMwhjWuicLRGU3M8JcYBg");
+        assert_eq!(result.from, "9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f".parse().unwrap());
+        assert_eq!(result.amount, 3000);
+        // the check is read-only and can be repeated
+        assert!(rpc.ledger.read().unwrap().get(&result.from).is_none());
+        assert_eq!(rpc.check_tx(ALICE_TX.parse().unwrap()).unwrap().amount, 3000);
+
+        // the accepted mapping is recorded
+        assert_eq!(rpc.map_tx(ALICE_TX.parse().unwrap()).unwrap().amount, 3000);
+        {
+            let ledger = rpc.ledger.read().unwrap();
+            let record = ledger.get(&result.from).unwrap();
+            assert_eq!(record.tx_hash, ALICE_TX.parse().unwrap());
+            assert_eq!((record.block_number, record.amount), (1, 3000));
+            assert_eq!(record.status, MappingStatus::Pending);
+            assert_eq!(record.proof.as_ref().unwrap().index, 2);
+        }
+        assert!(rpc.check_tx(ALICE_TX.parse().unwrap()).is_err());
+        assert!(rpc.map_tx(ALICE_TX.parse().unwrap()).is_err());
+        // the proof survives the ledger file and can be checked offline
+        let record = Ledger::open(path.clone()).unwrap().get(&result.from).cloned().unwrap();
+        assert_eq!(record.amount, 3000);
+        record.verify_proof().unwrap();
+
+        // bob is not in the snapshot
+        assert!(rpc.map_tx(BOB_TX.parse().unwrap()).is_err());
+        assert!(rpc.ledger.read().unwrap().get(&"b595b18c88b1f651ca387489067f855b5c8e6720".parse().unwrap()).is_none());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_check_signed_message() {
+        let (rpc, path) = rpc("signed-message");
+        let message = "5UdrXD14mzNMnosk5PAYVTbWjFKrMwhjWuicLRGU3M8JcYBg@memo".to_string();
+        // personal_sign by the key 0x4646..46
+        let signature = Bytes(hex::decode("7fc6c84dcae2e04cd08e093394cf62536349bd57218c0fb2ea63b7541ffaec9d09b47f1182308f6b113e732fb9071a46a2062e1aa9a9ef18abc6b904f24943031c").unwrap());
+        let owner: H160 = "9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f".parse().unwrap();
+
+        let result = rpc.check_signed_message(message.clone(), signature.clone(), owner).unwrap();
+        assert_eq!(result.amount, 3000);
+        let record = rpc.ledger.read().unwrap().get(&owner).cloned().unwrap();
+        assert_eq!(record.kind, MappingKind::SignedMessage);
+        assert_eq!(record.tx_hash, personal_message_hash(message.as_bytes()));
+        assert!(rpc.check_signed_message(message, signature, owner).is_err());
+        // the mapped address cannot map again by a transaction
+        assert!(rpc.check_tx(ALICE_TX.parse().unwrap()).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_check_raw_tx() {
+        let (rpc, path) = rpc("raw-tx");
+        let tx = FixtureSource::new(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures"))
+            .get_tx_by_hash(ALICE_TX.parse().unwrap())
+            .unwrap();
+        let raw = Bytes(UnverifiedTransaction::from(tx).encoded());
+
+        // the tx is not known by the source, e.g. never broadcast
+        let unmined = SdotRpc {
+            api: Box::new(FixtureSource::new(std::env::temp_dir())),
+            ..rpc
+        };
+        assert!(unmined.check_raw_tx(raw.clone()).is_err());
+        assert!(unmined.ledger.read().unwrap().get(&"9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f".parse().unwrap()).is_none());
+
+        let rpc = SdotRpc {
+            api: Box::new(FixtureSource::new(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures"))),
+            ..unmined
+        };
+        let result = rpc.check_raw_tx(raw.clone()).unwrap();
+        assert_eq!(result.amount, 3000);
+        assert!(rpc.ledger.read().unwrap().get(&result.from).is_none());
+        rpc.map_tx(ALICE_TX.parse().unwrap()).unwrap();
+        assert!(rpc.check_raw_tx(raw).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_check_typed_claim() {
+        let (rpc, path) = rpc("typed-claim");
+        // 1500 DOT of alice is 1000 SDOT
+        let rpc = SdotRpc {
+            calculator: PayoutCalculator::new(Ratio::new(2, 3), 3, 3),
+            ..rpc
+        };
+        let claim = Claim {
+            chainx_account: "5UdrXD14mzNMnosk5PAYVTbWjFKrMwhjWuicLRGU3M8JcYBg".to_string(),
+            amount: 1000.into(),
+            nonce: 1.into(),
+        };
+        // eth_signTypedData by the key 0x4646..46
+        let signature = Bytes(hex::decode("e9e369b72ef9fa2a68592f9840b6df4828daa441665551296d8427e367820ff151b2bbe4e33095dc4534934a3ae7c3ec8e7625e94e19844f032dd0522bdd8dbb1b").unwrap());
+        let owner: H160 = "9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f".parse().unwrap();
+        let domain = rpc.domain.clone().unwrap();
+
+        // the claimed amount must be the payout
+        let greedy = SdotRpc {
+            calculator: PayoutCalculator::new(Ratio::new(1, 3), 3, 3),
+            ..rpc
+        };
+        match greedy.check_typed_claim(domain.clone(), claim.clone(), signature.clone(), owner) {
+            Err(err) => assert_eq!(err.code, jsonrpc_core::ErrorCode::ServerError(10017)),
+            Ok(result) => panic!("unexpected result: {:?}", result),
+        }
+        assert!(greedy.ledger.read().unwrap().get(&owner).is_none());
+
+        let rpc = SdotRpc {
+            calculator: PayoutCalculator::new(Ratio::new(2, 3), 3, 3),
+            ..greedy
+        };
+        let result = rpc.check_typed_claim(domain.clone(), claim.clone(), signature.clone(), owner).unwrap();
+        assert_eq!(result.amount, 1000);
+        let record = rpc.ledger.read().unwrap().get(&owner).cloned().unwrap();
+        assert_eq!(record.kind, MappingKind::TypedClaim { nonce: 1.into() });
+        assert_eq!(record.tx_hash, claim.signing_hash(&domain));
+
+        // the nonce is kept by the ledger file
+        let ledger = Ledger::open(path.clone()).unwrap();
+        assert!(ledger.check_claim(&owner, 1.into()).is_err());
+        assert!(ledger.check_claim(&owner, 2.into()).is_ok());
+        assert!(rpc.check_typed_claim(domain.clone(), claim.clone(), signature.clone(), owner).is_err());
+
+        // the claims are rejected if the service is started without the claim contract
+        let disabled = SdotRpc { domain: None, ..rpc };
+        match disabled.check_typed_claim(domain, claim, signature, owner) {
+            Err(err) => assert_eq!(err.code, jsonrpc_core::ErrorCode::ServerError(10008)),
+            Ok(result) => panic!("unexpected result: {:?}", result),
+        }
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_default_serve_opt() {
+        // `serve` is run if no subcommand is given
+        let opt = ServeOpt::from_iter_safe(&["serve"]).unwrap();
+        assert_eq!(opt.claim_contract, None);
+        assert_eq!(config_url(&opt), "0.0.0.0:8100");
+    }
+}
@@ -0,0 +1,242 @@
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use chainx_primitives::AccountId;
+
+use crate::erc20::{check_token_tx, may_contain_transfer};
+use crate::error::{Error, Result};
+use crate::eth::check_tx;
+use crate::mapping::Mapping;
+use crate::source::TxSource;
+use crate::types::{FullTransaction, Receipt, H160};
+
+/// Walk the Ethereum blocks and discover the mapping transactions sent to the deposit address.
+///
+/// The number of the last processed block is saved in the checkpoint file,
+/// so that the scanning can be resumed after restarts.
+/// When following the chain head, only the blocks with enough confirmations are scanned.
+///
+/// With a token, the mapping transactions transfer the token to the deposit address instead,
+/// and the blocks whose logs bloom cannot contain such `Transfer` event are skipped.
+pub struct Scanner<S> {
+    source: S,
+    deposit: H160,
+    checkpoint: PathBuf,
+    confirmations: u64,
+    token: Option<H160>,
+}
+
+impl<S: TxSource> Scanner<S> {
+    pub fn new<P: Into<PathBuf>>(source: S, deposit: H160, checkpoint: P) -> Self {
+        Self {
+            source,
+            deposit,
+            checkpoint: checkpoint.into(),
+            confirmations: 1,
+            token: None,
+        }
+    }
+
+    /// Set the minimum number of confirmations of the blocks scanned by `follow`.
+    pub fn with_confirmations(mut self, confirmations: u64) -> Self {
+        self.confirmations = confirmations;
+        self
+    }
+
+    /// Scan the ERC-20 transfers of the `token` to the deposit address.
+    pub fn with_token(mut self, token: H160) -> Self {
+        self.token = Some(token);
+        self
+    }
+
+    pub fn source(&self) -> &S {
+        &self.source
+    }
+
+    /// The number of the last processed block, `None` if nothing has been processed.
+    ///
+    /// A malformed checkpoint is an error rather than a restart from the first block.
+    pub fn checkpoint(&self) -> Result<Option<u64>> {
+        if !self.checkpoint.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&self.checkpoint)?;
+        match content.trim().parse::<u64>() {
+            Ok(number) => Ok(Some(number)),
+            Err(_) => {
+                warn!("Invalid scanner checkpoint {:?}: {:?}", self.checkpoint, content);
+                Err(Error::InvalidCheckpoint(content))
+            }
+        }
+    }
+
+    fn save_checkpoint(&self, number: u64) -> Result<()> {
+        // Write to a temporary file first, so that a crash never leaves a truncated checkpoint.
+        let tmp = self.checkpoint.with_extension("tmp");
+        fs::write(&tmp, number.to_string())?;
+        fs::rename(&tmp, &self.checkpoint)?;
+        Ok(())
+    }
+
+    /// Scan the blocks `[from, to]`, skipping the blocks already processed according to the checkpoint.
+    ///
+    /// The checkpoint of a block is saved after all its mappings are handled,
+    /// an error of `on_mapping` stops the scan so that the block is scanned again by the next run.
+    pub fn scan_range<F: FnMut(Mapping) -> Result<()>>(&self, from: u64, to: u64, mut on_mapping: F) -> Result<()> {
+        let start = match self.checkpoint()? {
+            Some(last) if last >= from => last + 1,
+            _ => from,
+        };
+        for number in start..=to {
+            for mapping in self.scan_block(number)? {
+                on_mapping(mapping)?;
+            }
+            self.save_checkpoint(number)?;
+        }
+        Ok(())
+    }
+
+    /// The number of the latest block with enough confirmations.
+    pub fn confirmed_head(&self) -> Result<u64> {
+        let head = self.source.get_block_number()?;
+        Ok(head.saturating_sub(self.confirmations.saturating_sub(1)))
+    }
+
+    /// Follow the chain head, scanning the new confirmed blocks every `interval`. Never returns unless error.
+    pub fn follow<F: FnMut(Mapping) -> Result<()>>(&self, from: u64, interval: Duration, mut on_mapping: F) -> Result<()> {
+        loop {
+            let confirmed = self.confirmed_head()?;
+            self.scan_range(from, confirmed, &mut on_mapping)?;
+            thread::sleep(interval);
+        }
+    }
+
+    /// Return the valid mapping transactions in the block.
+    pub fn scan_block(&self, number: u64) -> Result<Vec<Mapping>> {
+        let block = self.source.get_block_by_number(number)?;
+        if let (Some(token), Some(bloom)) = (self.token, &block.logs_bloom) {
+            if !may_contain_transfer(bloom, token, self.deposit) {
+                debug!("Skip block #{}: no Transfer to the deposit address in the logs bloom", number);
+                return Ok(vec![]);
+            }
+        }
+        let target = self.token.unwrap_or(self.deposit);
+        let mut mappings = vec![];
+        for tx in block.transactions {
+            if tx.to != Some(target) {
+                continue;
+            }
+            let hash = tx.hash;
+            let receipt = self.source.get_receipt(hash)?;
+            match self.check_tx(tx.clone(), &receipt) {
+                Ok((who, from)) => {
+                    info!("Found mapping tx {:?} in block #{}: who: {:?} from: {:?}", hash, number, who, from);
+                    mappings.push(Mapping {
+                        who,
+                        from,
+                        hash,
+                        block_number: number,
+                        tx,
+                        receipt,
+                    });
+                }
+                Err(err) => warn!("Ignore invalid mapping tx {:?} in block #{}: {}", hash, number, err),
+            }
+        }
+        Ok(mappings)
+    }
+
+    fn check_tx(&self, tx: FullTransaction, receipt: &Receipt) -> Result<(AccountId, H160)> {
+        match self.token {
+            Some(token) => check_token_tx(tx, receipt, token, self.deposit),
+            None => check_tx(tx, receipt),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::FixtureSource;
+
+    fn scanner(name: &str) -> Scanner<FixtureSource> {
+        let checkpoint = std::env::temp_dir().join(format!("sdot-scanner-{}-{}", std::process::id(), name));
+        let _ = fs::remove_file(&checkpoint);
+        let source = FixtureSource::new(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures"));
+        Scanner::new(source, H160::repeat_byte(0x20), checkpoint)
+    }
+
+    fn scan(scanner: &Scanner<FixtureSource>, from: u64, to: u64) -> Vec<Mapping> {
+        let mut mappings = vec![];
+        scanner
+            .scan_range(from, to, |mapping| {
+                mappings.push(mapping);
+                Ok(())
+            })
+            .unwrap();
+        mappings
+    }
+
+    #[test]
+    fn test_scan_range() {
+        let scanner = scanner("range");
+        assert_eq!(scanner.checkpoint().unwrap(), None);
+
+        // the blob and set-code txs of block #1 are not sent to the deposit address
+        let mappings = scan(&scanner, 0, 1);
+        assert_eq!(mappings.len(), 1);
+        assert_eq!(mappings[0].block_number, 1);
+        assert_eq!(mappings[0].from, "9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f".parse().unwrap());
+        assert_eq!(scanner.checkpoint().unwrap(), Some(1));
+
+        // resume after the checkpoint
+        let mappings = scan(&scanner, 0, 2);
+        assert_eq!(mappings.len(), 1);
+        assert_eq!(mappings[0].block_number, 2);
+        assert_eq!(mappings[0].from, "b595b18c88b1f651ca387489067f855b5c8e6720".parse().unwrap());
+        assert_eq!(scanner.checkpoint().unwrap(), Some(2));
+        assert!(scan(&scanner, 0, 2).is_empty());
+        fs::remove_file(&scanner.checkpoint).unwrap();
+    }
+
+    #[test]
+    fn test_scan_range_from_checkpoint() {
+        let scanner = scanner("resume");
+        scanner.save_checkpoint(1).unwrap();
+        let mappings = scan(&scanner, 0, 2);
+        assert_eq!(mappings.iter().map(|m| m.block_number).collect::<Vec<_>>(), vec![2]);
+
+        // a checkpoint before `from` does not scan the earlier blocks
+        scanner.save_checkpoint(0).unwrap();
+        assert_eq!(scan(&scanner, 2, 2).len(), 1);
+        fs::remove_file(&scanner.checkpoint).unwrap();
+    }
+
+    #[test]
+    fn test_scan_range_error() {
+        let scanner = scanner("error");
+        scanner.save_checkpoint(0).unwrap();
+        match scanner.scan_range(0, 2, |_| Err(Error::MappingTimeout)) {
+            Err(Error::MappingTimeout) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        // the block of the failed mapping is scanned again
+        assert_eq!(scanner.checkpoint().unwrap(), Some(0));
+        assert_eq!(scan(&scanner, 0, 2).len(), 2);
+        fs::remove_file(&scanner.checkpoint).unwrap();
+    }
+
+    #[test]
+    fn test_malformed_checkpoint() {
+        let scanner = scanner("malformed");
+        fs::write(&scanner.checkpoint, "12ab").unwrap();
+        match scanner.scan_range(0, 2, |_| panic!("scanned with a malformed checkpoint")) {
+            Err(Error::InvalidCheckpoint(content)) => assert_eq!(content, "12ab"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert_eq!(fs::read_to_string(&scanner.checkpoint).unwrap(), "12ab");
+        fs::remove_file(&scanner.checkpoint).unwrap();
+    }
+}
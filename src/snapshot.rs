@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::types::H160;
+
+/// The amount of DOT, in the smallest unit recorded by the snapshot.
+pub type DotAmount = u128;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SnapshotFormat {
+    /// One hex address per line, without balance.
+    Text,
+    /// `address,balance` per line, the header line `address,balance` is optional.
+    Csv,
+    /// Array of `{"address": "0x..", "balance": "<decimal>"}`.
+    Json,
+}
+
+impl SnapshotFormat {
+    /// Guess the format from the file extension, plain text by default.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("csv") => SnapshotFormat::Csv,
+            Some("json") => SnapshotFormat::Json,
+            _ => SnapshotFormat::Text,
+        }
+    }
+}
+
+/// A duplicate or malformed entry of the snapshot.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct SnapshotIssue {
+    /// Line number, or entry number for the JSON format, starting from 1.
+    pub line: usize,
+    pub reason: String,
+}
+
+impl fmt::Display for SnapshotIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
+
+#[derive(Deserialize)]
+struct JsonOwner {
+    address: H160,
+    balance: Option<String>,
+}
+
+/// The snapshot of the eligible DOT owners.
+#[derive(Clone, Debug, Default)]
+pub struct OwnerSnapshot {
+    /// Owner address => entitled DOT amount, `None` if the snapshot has no balance.
+    owners: HashMap<H160, Option<DotAmount>>,
+    /// Owner address => line number, used to report the duplicates.
+    lines: HashMap<H160, usize>,
+}
+
+impl OwnerSnapshot {
+    /// Load the snapshot to pay the SDOT, fail if there is any duplicate or malformed entry,
+    /// or any owner without the DOT balance, e.g. a plain text snapshot.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let (snapshot, issues) = Self::load_lenient(path)?;
+        if !issues.is_empty() {
+            let issues = issues.iter().map(|issue| issue.to_string()).collect::<Vec<_>>();
+            return Err(Error::InvalidSnapshot(issues.join("; ")));
+        }
+        let missing = snapshot.owners.values().filter(|balance| balance.is_none()).count();
+        if missing > 0 {
+            return Err(Error::InvalidSnapshot(format!(
+                "{} of {} owners have no DOT balance, the payout needs a csv or json snapshot with balances",
+                missing,
+                snapshot.len()
+            )));
+        }
+        Ok(snapshot)
+    }
+
+    /// Load the snapshot, skip and return the duplicate or malformed entries.
+    pub fn load_lenient<P: AsRef<Path>>(path: P) -> Result<(Self, Vec<SnapshotIssue>)> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)?;
+        Self::parse(&content, SnapshotFormat::from_path(path))
+    }
+
+    pub fn parse(content: &str, format: SnapshotFormat) -> Result<(Self, Vec<SnapshotIssue>)> {
+        let mut snapshot = Self::default();
+        let mut issues = vec![];
+        if format == SnapshotFormat::Json {
+            // Parse the entries one by one, so that a malformed entry does not reject the whole file.
+            let entries: Vec<serde_json::Value> = serde_json::from_str(content)?;
+            for (index, entry) in entries.into_iter().enumerate() {
+                let entry = match serde_json::from_value::<JsonOwner>(entry) {
+                    Ok(entry) => entry,
+                    Err(err) => {
+                        let reason = format!("invalid entry: {}", err);
+                        issues.push(SnapshotIssue { line: index + 1, reason });
+                        continue;
+                    }
+                };
+                let balance = match entry.balance {
+                    Some(balance) => match parse_balance(&balance) {
+                        Ok(balance) => Some(balance),
+                        Err(reason) => {
+                            issues.push(SnapshotIssue { line: index + 1, reason });
+                            continue;
+                        }
+                    },
+                    None => None,
+                };
+                snapshot.insert(entry.address, balance, index + 1, &mut issues);
+            }
+            return Ok((snapshot, issues));
+        }
+
+        let mut first = true;
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if format == SnapshotFormat::Csv && std::mem::replace(&mut first, false) && is_csv_header(line) {
+                continue;
+            }
+            let entry = match format {
+                SnapshotFormat::Csv => parse_csv_line(line),
+                _ => parse_address(line).map(|address| (address, None)),
+            };
+            match entry {
+                Ok((address, balance)) => snapshot.insert(address, balance, index + 1, &mut issues),
+                Err(reason) => issues.push(SnapshotIssue { line: index + 1, reason }),
+            }
+        }
+        Ok((snapshot, issues))
+    }
+
+    fn insert(&mut self, address: H160, balance: Option<DotAmount>, line: usize, issues: &mut Vec<SnapshotIssue>) {
+        if let Some(first) = self.lines.get(&address) {
+            issues.push(SnapshotIssue {
+                line,
+                reason: format!("duplicate address {:?}, first seen at line {}", address, first),
+            });
+            return;
+        }
+        self.lines.insert(address, line);
+        self.owners.insert(address, balance);
+    }
+
+    pub fn is_owner(&self, address: &H160) -> bool {
+        self.owners.contains_key(address)
+    }
+
+    /// The entitled DOT amount, `None` if not an owner or the snapshot has no balance.
+    pub fn balance(&self, address: &H160) -> Option<DotAmount> {
+        self.owners.get(address).and_then(|balance| *balance)
+    }
+
+    pub fn len(&self) -> usize {
+        self.owners.len()
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.owners.is_empty()
+    }
+}
+
+fn parse_address(s: &str) -> std::result::Result<H160, String> {
+    let s = s.trim();
+    let bytes = hex::decode(s.trim_start_matches("0x")).map_err(|err| format!("invalid address '{}': {}", s, err))?;
+    if bytes.len() != 20 {
+        return Err(format!("invalid address '{}': expected 20 bytes", s));
+    }
+    Ok(H160::from_slice(&bytes))
+}
+
+fn parse_balance(s: &str) -> std::result::Result<DotAmount, String> {
+    let s = s.trim();
+    s.parse::<DotAmount>().map_err(|_| format!("invalid balance '{}'", s))
+}
+
+/// The header is only accepted as the first entry line, and must name exactly the two columns.
+fn is_csv_header(line: &str) -> bool {
+    let fields = line.split(',').map(|field| field.trim().to_lowercase()).collect::<Vec<_>>();
+    fields == ["address", "balance"]
+}
+
+fn parse_csv_line(line: &str) -> std::result::Result<(H160, Option<DotAmount>), String> {
+    let fields = line.split(',').collect::<Vec<_>>();
+    if fields.len() != 2 {
+        return Err(format!("expected 'address,balance', got {} fields", fields.len()));
+    }
+    Ok((parse_address(fields[0])?, Some(parse_balance(fields[1])?)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALICE: &str = "fac0dd475a6ca535fe64298fbf99a0c8950a4e08";
+    const BOB: &str = "e75787d8549c6fd95a2b8f431bbe7b3bf8c56daf";
+
+    fn address(s: &str) -> H160 {
+        parse_address(s).unwrap()
+    }
+
+    #[test]
+    fn test_parse_text_snapshot() {
+        let content = format!("{}\n\n0x{}\n", ALICE, BOB);
+        let (snapshot, issues) = OwnerSnapshot::parse(&content, SnapshotFormat::Text).unwrap();
+        assert!(issues.is_empty());
+        assert_eq!(snapshot.len(), 2);
+        assert!(snapshot.is_owner(&address(BOB)));
+        assert_eq!(snapshot.balance(&address(ALICE)), None);
+    }
+
+    #[test]
+    fn test_parse_csv_snapshot() {
+        let content = format!("address,balance\n{},100\n{},25\n{},7\nxyz,1\n{}\n", ALICE, BOB, ALICE, BOB);
+        let (snapshot, issues) = OwnerSnapshot::parse(&content, SnapshotFormat::Csv).unwrap();
+        assert_eq!(snapshot.len(), 2);
+        assert_eq!(snapshot.balance(&address(ALICE)), Some(100));
+        assert_eq!(snapshot.balance(&address(BOB)), Some(25));
+        assert_eq!(issues.iter().map(|issue| issue.line).collect::<Vec<_>>(), vec![4, 5, 6]);
+        assert!(issues[0].reason.contains("first seen at line 2"));
+
+        // the header after a comment, and a header-like line which is not the first entry
+        let content = format!("# owners\n Address , Balance\n{},100\naddress,balance\naddressbook,1\n", ALICE);
+        let (snapshot, issues) = OwnerSnapshot::parse(&content, SnapshotFormat::Csv).unwrap();
+        assert_eq!(snapshot.len(), 1);
+        assert_eq!(issues.iter().map(|issue| issue.line).collect::<Vec<_>>(), vec![4, 5]);
+        let (_, issues) = OwnerSnapshot::parse("addressbook,1\n", SnapshotFormat::Csv).unwrap();
+        assert_eq!(issues.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_json_snapshot() {
+        let content = format!(
+            r#"[{{"address": "0x{}", "balance": "100"}}, {{"address": "0x{}", "balance": "-1"}}, {{"address": "0x12"}}, 7, {{"address": "0x{}"}}]"#,
+            ALICE, BOB, BOB
+        );
+        let (snapshot, issues) = OwnerSnapshot::parse(&content, SnapshotFormat::Json).unwrap();
+        assert_eq!(snapshot.balance(&address(ALICE)), Some(100));
+        assert!(snapshot.is_owner(&address(BOB)));
+        assert_eq!(snapshot.balance(&address(BOB)), None);
+        assert_eq!(issues.iter().map(|issue| issue.line).collect::<Vec<_>>(), vec![2, 3, 4]);
+        assert!(issues[1].reason.starts_with("invalid entry"));
+        assert!(OwnerSnapshot::parse("{}", SnapshotFormat::Json).is_err());
+    }
+
+    #[test]
+    fn test_load_snapshot_without_balance() {
+        let dir = std::env::temp_dir();
+        let text = dir.join(format!("sdot-owners-{}.txt", std::process::id()));
+        fs::write(&text, format!("{}\n{}\n", ALICE, BOB)).unwrap();
+        match OwnerSnapshot::load(&text) {
+            Err(Error::InvalidSnapshot(reason)) => assert!(reason.starts_with("2 of 2 owners have no DOT balance")),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        // the snapshot without balance is still valid for the snapshot subcommand
+        assert_eq!(OwnerSnapshot::load_lenient(&text).unwrap().0.len(), 2);
+
+        let csv = dir.join(format!("sdot-owners-{}.csv", std::process::id()));
+        fs::write(&csv, format!("{},100\n{},25\n", ALICE, BOB)).unwrap();
+        assert_eq!(OwnerSnapshot::load(&csv).unwrap().balance(&address(BOB)), Some(25));
+
+        fs::remove_file(&text).unwrap();
+        fs::remove_file(&csv).unwrap();
+    }
+}
@@ -0,0 +1,183 @@
+use serde::de::DeserializeOwned;
+
+use crate::error::{Error, Result};
+use crate::types::{FullBlock, FullTransaction, Receipt, H256, U64};
+
+use super::{RpcResponse, TxSource};
+
+pub static ETHERSCAN_API_URL: &str = "http://api-cn.etherscan.com/api";
+
+#[derive(Clone)]
+pub struct EtherScanApi {
+    client: reqwest::Client,
+    url: String,
+    /// The requests without API key are heavily rate limited by EtherScan.
+    api_key: Option<String>,
+}
+
+impl EtherScanApi {
+    pub fn new(api_key: Option<String>) -> Self {
+        Self::with_api(ETHERSCAN_API_URL, api_key)
+    }
+
+    pub fn with_api<S: Into<String>>(url: S, api_key: Option<String>) -> Self {
+        if api_key.is_none() {
+            warn!("No EtherScan API key, the requests are rate limited");
+        }
+        Self {
+            client: reqwest::Client::new(),
+            url: url.into(),
+            api_key,
+        }
+    }
+
+    pub fn get_tx_by_hash(&self, hash: H256) -> Result<FullTransaction> {
+        match self.get_tx_by_hash_impl(hash) {
+            Ok(transaction) => match transaction {
+                Some(transaction) => Ok(transaction),
+                None => {
+                    warn!("Non-existent Ethereum transaction");
+                    Err(Error::NonExistentEthTx)
+                }
+            },
+            Err(err @ Error::EthRpc(..)) => Err(err),
+            Err(err) => {
+                error!("EtherScanApi get Ethereum tx error: {:?}", err);
+                Err(Error::EtherScanCannotGetTx)
+            }
+        }
+    }
+
+    pub fn get_tx_by_block_num(&self, block_num: u64) -> Result<FullBlock> {
+        match self.get_tx_by_block_impl(block_num) {
+            Ok(block) => match block {
+                Some(block) => Ok(block),
+                None => {
+                    warn!("Non-existent Ethereum block #{}", block_num);
+                    Err(Error::NonExistentEthBlock)
+                }
+            },
+            Err(err @ Error::EthRpc(..)) => Err(err),
+            Err(err) => {
+                error!("EtherScanApi get Ethereum block error: {:?}", err);
+                Err(Error::EtherScanCannotGetTx)
+            }
+        }
+    }
+
+    pub fn get_receipt(&self, hash: H256) -> Result<Receipt> {
+        match self.proxy("eth_getTransactionReceipt", &[("txhash", format!("{:?}", hash))]) {
+            Ok(receipt) => match receipt {
+                Some(receipt) => Ok(receipt),
+                None => {
+                    warn!("Non-existent Ethereum transaction receipt");
+                    Err(Error::NonExistentEthTx)
+                }
+            },
+            Err(err @ Error::EthRpc(..)) => Err(err),
+            Err(err) => {
+                error!("EtherScanApi get Ethereum tx receipt error: {:?}", err);
+                Err(Error::EtherScanCannotGetTx)
+            }
+        }
+    }
+
+    pub fn get_block_number(&self) -> Result<u64> {
+        match self.proxy::<U64>("eth_blockNumber", &[]) {
+            Ok(Some(number)) => Ok(number.as_u64()),
+            Ok(None) => Err(Error::NonExistentEthBlock),
+            Err(err @ Error::EthRpc(..)) => Err(err),
+            Err(err) => {
+                error!("EtherScanApi get Ethereum block number error: {:?}", err);
+                Err(Error::EtherScanCannotGetTx)
+            }
+        }
+    }
+
+    pub fn get_tx_by_hash_impl(&self, hash: H256) -> Result<Option<FullTransaction>> {
+        self.proxy("eth_getTransactionByHash", &[("txhash", format!("{:?}", hash))])
+    }
+
+    pub fn get_tx_by_block_impl(&self, block_num: u64) -> Result<Option<FullBlock>> {
+        let params = [
+            ("tag", format!("{:#x}", block_num)),
+            ("boolean", "true".to_string()),
+        ];
+        self.proxy("eth_getBlockByNumber", &params)
+    }
+
+    /// Call the JSON-RPC `action` through the EtherScan proxy module.
+    fn proxy<T: DeserializeOwned>(&self, action: &str, params: &[(&str, String)]) -> Result<Option<T>> {
+        let mut request = self
+            .client
+            .get(&self.url)
+            .query(&[("module", "proxy"), ("action", action)])
+            .query(params);
+        if let Some(ref api_key) = self.api_key {
+            request = request.query(&[("apikey", api_key.as_str())]);
+        }
+        request.send()?.json::<RpcResponse<T>>()?.into_result()
+    }
+}
+
+impl TxSource for EtherScanApi {
+    fn get_block_number(&self) -> Result<u64> {
+        EtherScanApi::get_block_number(self)
+    }
+
+    fn get_tx_by_hash(&self, hash: H256) -> Result<FullTransaction> {
+        EtherScanApi::get_tx_by_hash(self, hash)
+    }
+
+    fn get_block_by_number(&self, number: u64) -> Result<FullBlock> {
+        self.get_tx_by_block_num(number)
+    }
+
+    fn get_receipt(&self, hash: H256) -> Result<Receipt> {
+        EtherScanApi::get_receipt(self, hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eth::check_tx;
+    use hex_literal::hex;
+
+    fn api() -> EtherScanApi {
+        EtherScanApi::new(std::env::var("ETHERSCAN_API_KEY").ok())
+    }
+
+    #[test]
+    fn test_rpc_error() {
+        let response: RpcResponse<FullTransaction> = serde_json::from_str(
+            r#"{"jsonrpc": "2.0", "id": 1, "error": {"code": -32602, "message": "invalid argument 0"}}"#,
+        )
+        .unwrap();
+        match response.into_result() {
+            Err(Error::EthRpc(-32602, ref message)) if message == "invalid argument 0" => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let response: RpcResponse<FullTransaction> =
+            serde_json::from_str(r#"{"jsonrpc": "2.0", "id": 1, "result": null}"#).unwrap();
+        assert_eq!(response.into_result().unwrap(), None);
+    }
+
+    #[test]
+    #[ignore] // requires the network
+    fn test_etherscan_txhash_api() {
+        let tx = api()
+            .get_tx_by_hash(H256::from(&hex!("09146acd857bf292907934839f99ab41ecede9a4dbaacfcda043ddfde1f270d5")))
+            .unwrap();
+        let receipt = api().get_receipt(tx.hash).unwrap();
+        check_tx(tx, &receipt).unwrap();
+    }
+
+    #[test]
+    #[ignore] // requires the network
+    fn test_etherscan_block_api() {
+        let block = api().get_tx_by_block_num(5466).unwrap();
+        assert_eq!(block.number, Some(5466.into()));
+    }
+}
@@ -0,0 +1,106 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+
+use serde::de::DeserializeOwned;
+
+use crate::error::{Error, Result};
+use crate::types::{Bytes, FullBlock, FullTransaction, Receipt, H256};
+
+use super::{RpcResponse, TxSource};
+
+/// Read the recorded JSON-RPC responses from a fixture directory:
+///
+/// - `tx/<hash>.json` for `eth_getTransactionByHash`
+/// - `block/<number>.json` for `eth_getBlockByNumber`
+/// - `receipt/<hash>.json` for `eth_getTransactionReceipt`
+/// - `raw/<hash>.json` for `eth_getRawTransactionByHash`
+///
+/// The hash is lower hex without `0x` prefix and the block number is decimal.
+#[derive(Clone, Debug)]
+pub struct FixtureSource {
+    dir: PathBuf,
+}
+
+impl FixtureSource {
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn read<T: DeserializeOwned>(&self, kind: &str, name: String) -> Result<Option<T>> {
+        let path = self.dir.join(kind).join(format!("{}.json", name));
+        if !path.exists() {
+            return Ok(None);
+        }
+        let reader = BufReader::new(File::open(path)?);
+        let response: RpcResponse<T> = serde_json::from_reader(reader)?;
+        response.into_result()
+    }
+}
+
+impl TxSource for FixtureSource {
+    /// The highest recorded block is regarded as the chain head.
+    fn get_block_number(&self) -> Result<u64> {
+        let mut head = None;
+        for entry in std::fs::read_dir(self.dir.join("block"))? {
+            let path = entry?.path();
+            let number = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.parse::<u64>().ok());
+            head = head.max(number);
+        }
+        head.ok_or(Error::NonExistentEthBlock)
+    }
+
+    fn get_tx_by_hash(&self, hash: H256) -> Result<FullTransaction> {
+        match self.read("tx", format!("{:x}", hash))? {
+            Some(transaction) => Ok(transaction),
+            None => Err(Error::NonExistentEthTx),
+        }
+    }
+
+    fn get_block_by_number(&self, number: u64) -> Result<FullBlock> {
+        match self.read("block", number.to_string())? {
+            Some(block) => Ok(block),
+            None => Err(Error::NonExistentEthBlock),
+        }
+    }
+
+    fn get_receipt(&self, hash: H256) -> Result<Receipt> {
+        match self.read("receipt", format!("{:x}", hash))? {
+            Some(receipt) => Ok(receipt),
+            None => Err(Error::NonExistentEthTx),
+        }
+    }
+
+    fn get_raw_tx(&self, hash: H256) -> Result<Bytes> {
+        match self.read("raw", format!("{:x}", hash))? {
+            Some(raw) => Ok(raw),
+            None => Err(Error::NonExistentEthTx),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixture_block() {
+        let source = FixtureSource::new(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures"));
+        let genesis = source.get_block_by_number(0).unwrap();
+        assert_eq!(genesis.number.unwrap().as_u64(), 0);
+        assert_eq!(
+            format!("{:x}", genesis.hash.unwrap()),
+            "d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3"
+        );
+        assert!(genesis.transactions.is_empty());
+        assert!(source.get_block_number().is_ok());
+
+        match source.get_block_by_number(u64::MAX) {
+            Err(Error::NonExistentEthBlock) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
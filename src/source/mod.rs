@@ -0,0 +1,79 @@
+mod etherscan;
+mod fixture;
+mod node;
+
+pub use self::etherscan::EtherScanApi;
+pub use self::fixture::FixtureSource;
+pub use self::node::NodeSource;
+
+use serde::Deserialize;
+
+use crate::error::{Error, Result};
+use crate::types::{Bytes, FullBlock, FullTransaction, Receipt, H256};
+
+/// The error object of the Ethereum JSON-RPC response.
+#[derive(Deserialize, Debug)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+/// The response of the Ethereum JSON-RPC, the EtherScan proxy module returns the same format.
+#[derive(Deserialize, Debug)]
+struct RpcResponse<T> {
+    result: Option<T>,
+    error: Option<RpcError>,
+}
+
+impl<T> RpcResponse<T> {
+    /// The result, `None` for the non-existent object, or the error returned by the node.
+    fn into_result(self) -> Result<Option<T>> {
+        match self.error {
+            Some(error) => {
+                warn!("Ethereum JSON-RPC error {}: {}", error.code, error.message);
+                Err(Error::EthRpc(error.code, error.message))
+            }
+            None => Ok(self.result),
+        }
+    }
+}
+
+/// Where the Ethereum transactions, blocks and receipts come from.
+pub trait TxSource {
+    /// Get the number of the most recent block (`eth_blockNumber`).
+    fn get_block_number(&self) -> Result<u64>;
+    /// Get the transaction by hash (`eth_getTransactionByHash`).
+    fn get_tx_by_hash(&self, hash: H256) -> Result<FullTransaction>;
+    /// Get the block with full transactions by number (`eth_getBlockByNumber`).
+    fn get_block_by_number(&self, number: u64) -> Result<FullBlock>;
+    /// Get the receipt of the transaction (`eth_getTransactionReceipt`).
+    fn get_receipt(&self, hash: H256) -> Result<Receipt>;
+    /// Get the signed transaction bytes (`eth_getRawTransactionByHash`),
+    /// which the transaction types unknown to `TxType` are verified with.
+    fn get_raw_tx(&self, hash: H256) -> Result<Bytes> {
+        warn!("The source cannot get the raw Ethereum tx {:?}", hash);
+        Err(Error::NonExistentEthTx)
+    }
+}
+
+impl<T: TxSource + ?Sized> TxSource for Box<T> {
+    fn get_block_number(&self) -> Result<u64> {
+        (**self).get_block_number()
+    }
+
+    fn get_tx_by_hash(&self, hash: H256) -> Result<FullTransaction> {
+        (**self).get_tx_by_hash(hash)
+    }
+
+    fn get_block_by_number(&self, number: u64) -> Result<FullBlock> {
+        (**self).get_block_by_number(number)
+    }
+
+    fn get_receipt(&self, hash: H256) -> Result<Receipt> {
+        (**self).get_receipt(hash)
+    }
+
+    fn get_raw_tx(&self, hash: H256) -> Result<Bytes> {
+        (**self).get_raw_tx(hash)
+    }
+}
@@ -0,0 +1,88 @@
+use serde::de::DeserializeOwned;
+use web3::futures::Future;
+use web3::transports::{EventLoopHandle, Http};
+use web3::Transport;
+
+use crate::error::{Error, Result};
+use crate::types::{Bytes, FullBlock, FullTransaction, Receipt, H256, U64};
+
+use super::TxSource;
+
+/// Fetch data from an Ethereum node through the plain JSON-RPC interface.
+pub struct NodeSource {
+    // The event loop must be kept alive as long as the transport is used.
+    _eloop: EventLoopHandle,
+    transport: Http,
+}
+
+impl NodeSource {
+    pub fn new(url: &str) -> Result<Self> {
+        let (eloop, transport) = Http::new(url)?;
+        Ok(Self {
+            _eloop: eloop,
+            transport,
+        })
+    }
+
+    fn call<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Vec<serde_json::Value>,
+    ) -> Result<Option<T>> {
+        let value = self.transport.execute(method, params).wait()?;
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+impl TxSource for NodeSource {
+    fn get_block_number(&self) -> Result<u64> {
+        match self.call::<U64>("eth_blockNumber", vec![])? {
+            Some(number) => Ok(number.as_u64()),
+            None => Err(Error::NonExistentEthBlock),
+        }
+    }
+
+    fn get_tx_by_hash(&self, hash: H256) -> Result<FullTransaction> {
+        let params = vec![serde_json::to_value(hash)?];
+        match self.call("eth_getTransactionByHash", params)? {
+            Some(transaction) => Ok(transaction),
+            None => {
+                warn!("Non-existent Ethereum transaction");
+                Err(Error::NonExistentEthTx)
+            }
+        }
+    }
+
+    fn get_block_by_number(&self, number: u64) -> Result<FullBlock> {
+        let params = vec![format!("{:#x}", number).into(), true.into()];
+        match self.call("eth_getBlockByNumber", params)? {
+            Some(block) => Ok(block),
+            None => {
+                warn!("Non-existent Ethereum block #{}", number);
+                Err(Error::NonExistentEthBlock)
+            }
+        }
+    }
+
+    fn get_receipt(&self, hash: H256) -> Result<Receipt> {
+        let params = vec![serde_json::to_value(hash)?];
+        match self.call("eth_getTransactionReceipt", params)? {
+            Some(receipt) => Ok(receipt),
+            None => {
+                warn!("Non-existent Ethereum transaction receipt");
+                Err(Error::NonExistentEthTx)
+            }
+        }
+    }
+
+    fn get_raw_tx(&self, hash: H256) -> Result<Bytes> {
+        let params = vec![serde_json::to_value(hash)?];
+        match self.call("eth_getRawTransactionByHash", params)? {
+            Some(raw) => Ok(raw),
+            None => {
+                warn!("Non-existent Ethereum transaction");
+                Err(Error::NonExistentEthTx)
+            }
+        }
+    }
+}
@@ -0,0 +1,353 @@
+use rlp::{Rlp, RlpStream};
+
+use crate::types::{keccak, H256};
+
+/// The node of the Merkle-Patricia trie, the keys are in nibbles.
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Empty,
+    Leaf(Vec<u8>, Vec<u8>),
+    Extension(Vec<u8>, Box<Node>),
+    /// 16 children and the value of the key ending at the branch.
+    Branch(Vec<Node>, Option<Vec<u8>>),
+}
+
+impl Node {
+    /// Build the node from the items sorted by key, the first `depth` nibbles of the keys are shared.
+    fn build(items: &[(Vec<u8>, Vec<u8>)], depth: usize) -> Node {
+        match items.len() {
+            0 => return Node::Empty,
+            1 => return Node::Leaf(items[0].0[depth..].to_vec(), items[0].1.clone()),
+            _ => {}
+        }
+
+        // The items are sorted, so the prefix shared by the first and the last is shared by all.
+        let first = &items[0].0[depth..];
+        let last = &items[items.len() - 1].0[depth..];
+        let shared = first.iter().zip(last).take_while(|(a, b)| a == b).count();
+        if shared > 0 {
+            let child = Node::build(items, depth + shared);
+            return Node::Extension(first[..shared].to_vec(), Box::new(child));
+        }
+
+        let (value, items) = if items[0].0.len() == depth {
+            (Some(items[0].1.clone()), &items[1..])
+        } else {
+            (None, items)
+        };
+        let mut children = Vec::with_capacity(16);
+        let mut start = 0;
+        for nibble in 0..16u8 {
+            let end = start + items[start..].iter().take_while(|(key, _)| key[depth] == nibble).count();
+            children.push(Node::build(&items[start..end], depth + 1));
+            start = end;
+        }
+        Node::Branch(children, value)
+    }
+
+    /// The RLP encoding of the node.
+    fn encode(&self) -> Vec<u8> {
+        let mut stream = RlpStream::new();
+        match self {
+            Node::Empty => {
+                stream.append_empty_data();
+            }
+            Node::Leaf(key, value) => {
+                stream.begin_list(2);
+                stream.append(&hex_prefix(key, true));
+                stream.append(value);
+            }
+            Node::Extension(key, child) => {
+                stream.begin_list(2);
+                stream.append(&hex_prefix(key, false));
+                child.append_ref(&mut stream);
+            }
+            Node::Branch(children, value) => {
+                stream.begin_list(17);
+                for child in children {
+                    child.append_ref(&mut stream);
+                }
+                match value {
+                    Some(value) => stream.append(value),
+                    None => stream.append_empty_data(),
+                };
+            }
+        }
+        stream.out()
+    }
+
+    /// Append the reference of the child node: inline if the encoding is shorter than 32 bytes, otherwise the hash.
+    fn append_ref(&self, stream: &mut RlpStream) {
+        if let Node::Empty = self {
+            stream.append_empty_data();
+            return;
+        }
+        let encoded = self.encode();
+        if encoded.len() < 32 {
+            stream.append_raw(&encoded, 1);
+        } else {
+            stream.append(&keccak(&encoded));
+        }
+    }
+}
+
+/// The in-memory Merkle-Patricia trie, used to compute the roots of the block.
+#[derive(Debug, Clone)]
+pub struct Trie {
+    root: Node,
+}
+
+impl Trie {
+    /// Build the trie of the key-value pairs, the later value wins for the duplicate keys.
+    pub fn new(pairs: Vec<(Vec<u8>, Vec<u8>)>) -> Self {
+        let mut items = pairs
+            .into_iter()
+            .map(|(key, value)| (to_nibbles(&key), value))
+            .collect::<Vec<_>>();
+        // Stable sort keeps the insertion order of the duplicate keys.
+        items.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut deduped: Vec<(Vec<u8>, Vec<u8>)> = Vec::with_capacity(items.len());
+        for item in items {
+            match deduped.last_mut() {
+                Some(last) if last.0 == item.0 => *last = item,
+                _ => deduped.push(item),
+            }
+        }
+        Self {
+            root: Node::build(&deduped, 0),
+        }
+    }
+
+    /// Build the trie of the items keyed by the RLP encoded index, like the transactions and receipts of the block.
+    pub fn ordered<I, V>(items: I) -> Self
+    where
+        I: IntoIterator<Item = V>,
+        V: AsRef<[u8]>,
+    {
+        let pairs = items
+            .into_iter()
+            .enumerate()
+            .map(|(index, item)| (rlp::encode(&(index as u64)), item.as_ref().to_vec()))
+            .collect();
+        Self::new(pairs)
+    }
+
+    pub fn root(&self) -> H256 {
+        keccak(self.root.encode())
+    }
+
+    /// The proof of the key: the root node and the nodes referenced by hash on the path to the value,
+    /// `None` if the key is not in the trie.
+    pub fn prove(&self, key: &[u8]) -> Option<Vec<Vec<u8>>> {
+        let key = to_nibbles(key);
+        let mut rest = &key[..];
+        let mut node = &self.root;
+        let mut proof = vec![];
+        loop {
+            let encoded = node.encode();
+            // The nodes shorter than 32 bytes are inlined in the parent node.
+            if proof.is_empty() || encoded.len() >= 32 {
+                proof.push(encoded);
+            }
+            match node {
+                Node::Empty => return None,
+                Node::Leaf(path, _) => return if path[..] == *rest { Some(proof) } else { None },
+                Node::Extension(path, child) => {
+                    if !rest.starts_with(path) {
+                        return None;
+                    }
+                    rest = &rest[path.len()..];
+                    node = child;
+                }
+                Node::Branch(children, value) => {
+                    if rest.is_empty() {
+                        return value.as_ref().map(|_| proof);
+                    }
+                    node = &children[rest[0] as usize];
+                    rest = &rest[1..];
+                }
+            }
+        }
+    }
+
+    /// The proof of the item at `index` of the ordered trie.
+    pub fn prove_index(&self, index: usize) -> Option<Vec<Vec<u8>>> {
+        self.prove(&rlp::encode(&(index as u64)))
+    }
+}
+
+/// Verify the proof of the key against the trie root, return the proven value.
+///
+/// `None` if the proof is invalid or proves the absence of the key.
+pub fn verify_proof(root: &H256, key: &[u8], proof: &[Vec<u8>]) -> Option<Vec<u8>> {
+    let key = to_nibbles(key);
+    let mut rest = &key[..];
+    let first = proof.first()?;
+    if keccak(first) != *root {
+        return None;
+    }
+    let mut used = 1;
+    let mut node = first.clone();
+    loop {
+        let next = {
+            let rlp = Rlp::new(&node);
+            let child = match rlp.item_count().ok()? {
+                2 => {
+                    let (path, leaf) = decode_hex_prefix(rlp.at(0).ok()?.data().ok()?)?;
+                    if leaf {
+                        return if path[..] == *rest {
+                            Some(rlp.at(1).ok()?.data().ok()?.to_vec())
+                        } else {
+                            None
+                        };
+                    }
+                    if !rest.starts_with(&path) {
+                        return None;
+                    }
+                    rest = &rest[path.len()..];
+                    rlp.at(1).ok()?
+                }
+                17 => {
+                    if rest.is_empty() {
+                        let value = rlp.at(16).ok()?.data().ok()?;
+                        return if value.is_empty() { None } else { Some(value.to_vec()) };
+                    }
+                    let child = rlp.at(rest[0] as usize).ok()?;
+                    rest = &rest[1..];
+                    child
+                }
+                _ => return None,
+            };
+            if child.is_list() {
+                child.as_raw().to_vec()
+            } else {
+                let hash = child.data().ok()?;
+                let next = proof.get(used)?;
+                if hash.len() != 32 || keccak(next).as_bytes() != hash {
+                    return None;
+                }
+                used += 1;
+                next.clone()
+            }
+        };
+        node = next;
+    }
+}
+
+/// Verify the proof of the item at `index` of the ordered trie.
+pub fn verify_index_proof(root: &H256, index: usize, proof: &[Vec<u8>]) -> Option<Vec<u8>> {
+    verify_proof(root, &rlp::encode(&(index as u64)), proof)
+}
+
+/// The trie root of the items keyed by the RLP encoded index.
+pub fn ordered_trie_root<I, V>(items: I) -> H256
+where
+    I: IntoIterator<Item = V>,
+    V: AsRef<[u8]>,
+{
+    Trie::ordered(items).root()
+}
+
+fn to_nibbles(key: &[u8]) -> Vec<u8> {
+    key.iter().flat_map(|b| vec![b >> 4, b & 0x0f]).collect()
+}
+
+fn decode_hex_prefix(encoded: &[u8]) -> Option<(Vec<u8>, bool)> {
+    let (first, rest) = encoded.split_first()?;
+    let leaf = first & 0x20 != 0;
+    let mut nibbles = if first & 0x10 != 0 { vec![first & 0x0f] } else { vec![] };
+    nibbles.extend(to_nibbles(rest));
+    Some((nibbles, leaf))
+}
+
+/// The compact encoding of the nibbles, with the flag of the leaf and the odd length.
+fn hex_prefix(nibbles: &[u8], leaf: bool) -> Vec<u8> {
+    let flag = if leaf { 0x20 } else { 0x00 };
+    let mut encoded = Vec::with_capacity(nibbles.len() / 2 + 1);
+    let rest = if nibbles.len() % 2 == 1 {
+        encoded.push(flag | 0x10 | nibbles[0]);
+        &nibbles[1..]
+    } else {
+        encoded.push(flag);
+        nibbles
+    };
+    for pair in rest.chunks(2) {
+        encoded.push(pair[0] << 4 | pair[1]);
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+
+    fn trie(pairs: &[(&str, &str)]) -> Trie {
+        Trie::new(
+            pairs
+                .iter()
+                .map(|(key, value)| (key.as_bytes().to_vec(), value.as_bytes().to_vec()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_empty_trie_root() {
+        let empty: Vec<Vec<u8>> = vec![];
+        assert_eq!(
+            ordered_trie_root(empty),
+            H256::from(hex!("56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421"))
+        );
+    }
+
+    #[test]
+    fn test_trie_root() {
+        // The tests of trieanyorder.json in ethereum/tests
+        let puppy = trie(&[("do", "verb"), ("horse", "stallion"), ("doge", "coin"), ("dog", "puppy")]);
+        assert_eq!(
+            puppy.root(),
+            H256::from(hex!("5991bb8c6514148a29db676a14ac506cd2cd5775ace63c30a4fe457715e9ac84"))
+        );
+        let dogs = trie(&[("doe", "reindeer"), ("dog", "puppy"), ("dogglesworth", "cat")]);
+        assert_eq!(
+            dogs.root(),
+            H256::from(hex!("8aad789dff2f538bca5d8ea56e8abe10f4c7ba3a5dea95fea4cd6e7c3a1168d3"))
+        );
+        let replaced = trie(&[("dog", "cat"), ("doe", "reindeer"), ("dog", "puppy"), ("dogglesworth", "cat")]);
+        assert_eq!(replaced.root(), dogs.root());
+    }
+
+    #[test]
+    fn test_prove_and_verify() {
+        let pairs = [("do", "verb"), ("horse", "stallion"), ("doge", "coin"), ("dog", "puppy")];
+        let puppy = trie(&pairs);
+        let root = puppy.root();
+        for (key, value) in pairs.iter() {
+            let proof = puppy.prove(key.as_bytes()).unwrap();
+            assert_eq!(verify_proof(&root, key.as_bytes(), &proof), Some(value.as_bytes().to_vec()));
+        }
+        assert_eq!(puppy.prove(b"doe"), None);
+
+        let proof = puppy.prove(b"dog").unwrap();
+        assert_eq!(verify_proof(&root, b"doe", &proof), None);
+        assert_eq!(verify_proof(&H256::zero(), b"dog", &proof), None);
+        let mut tampered = proof.clone();
+        let last = tampered.last_mut().unwrap();
+        let len = last.len();
+        last[len - 1] ^= 1;
+        assert_eq!(verify_proof(&root, b"dog", &tampered), None);
+    }
+
+    #[test]
+    fn test_prove_ordered_items() {
+        let items = (0..300u32).map(|i| vec![(i % 251) as u8; 40 + (i % 7) as usize]).collect::<Vec<_>>();
+        let trie = Trie::ordered(&items);
+        let root = trie.root();
+        for &index in [0usize, 1, 127, 128, 299].iter() {
+            let proof = trie.prove_index(index).unwrap();
+            assert_eq!(verify_index_proof(&root, index, &proof), Some(items[index].clone()));
+            assert_eq!(verify_index_proof(&root, index + 1, &proof), None);
+        }
+        assert_eq!(trie.prove_index(300), None);
+    }
+}
@@ -1,12 +1,7 @@
 use super::*;
 use serde::{Deserialize, Serialize};
-use std::ops;
-use fixed_hash::*;
-#[cfg(feature = "serialize")]
-use impl_serde::impl_fixed_hash_serde;
 use impl_rlp::impl_fixed_hash_rlp;
 
-use parity_codec::{Decode, Encode};
 use rlp::{Rlp, RlpStream, Encodable, DecoderError, Decodable};
 
 /// Vector of bytes.
@@ -19,15 +14,71 @@ pub type Address = H160;
 const BLOOM_BITS: u32 = 3;
 const BLOOM_SIZE: usize = 256;
 
-construct_fixed_hash!{
-	/// Bloom hash type with 256 bytes (2048 bits) size.
-	pub struct Bloom(BLOOM_SIZE);
+// The macro of fixed-hash drops the result of a `Hasher::finish`.
+#[allow(unused_must_use)]
+mod bloom {
+    use fixed_hash::*;
+
+    construct_fixed_hash!{
+        /// Bloom hash type with 256 bytes (2048 bits) size.
+        pub struct Bloom(super::BLOOM_SIZE);
+    }
 }
+pub use self::bloom::Bloom;
 impl_fixed_hash_rlp!(Bloom, BLOOM_SIZE);
 
+impl Bloom {
+    /// Add the input to the bloom, following the `M3:2048` of the yellow paper:
+    /// set the 3 bits selected by the low 11 bits of the first 3 byte pairs of the keccak hash.
+    pub fn accrue(&mut self, input: &[u8]) {
+        let hash = keccak(input);
+        for i in 0..BLOOM_BITS as usize {
+            let bit = (usize::from(hash[2 * i]) << 8 | usize::from(hash[2 * i + 1])) & (BLOOM_SIZE * 8 - 1);
+            self.0[BLOOM_SIZE - 1 - bit / 8] |= 1 << (bit % 8);
+        }
+    }
+
+    /// Add all the bits of the other bloom.
+    pub fn accrue_bloom(&mut self, bloom: &Bloom) {
+        for (byte, other) in self.0.iter_mut().zip(bloom.0.iter()) {
+            *byte |= other;
+        }
+    }
+
+    /// Whether the input may have been added, false positive is possible but false negative is not.
+    pub fn contains_input(&self, input: &[u8]) -> bool {
+        let mut bloom = Bloom::zero();
+        bloom.accrue(input);
+        self.contains_bloom(&bloom)
+    }
+
+    /// Whether all the bits of the other bloom are set.
+    pub fn contains_bloom(&self, bloom: &Bloom) -> bool {
+        self.0.iter().zip(bloom.0.iter()).all(|(byte, other)| byte & other == *other)
+    }
+}
+
+impl Serialize for Bloom {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("0x{}", hex::encode(self.as_bytes())))
+    }
+}
+
+impl<'de> Deserialize<'de> for Bloom {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = super::Bytes::deserialize(deserializer)?;
+        if bytes.0.len() != BLOOM_SIZE {
+            return Err(serde::de::Error::invalid_length(bytes.0.len(), &"256 bytes"));
+        }
+        Ok(Bloom::from_slice(&bytes.0))
+    }
+}
+
+#[allow(dead_code)]
 pub type Public = H512;
 /// Helper structure, used for encoding blocks.
-#[derive(Default, Clone)]
+#[allow(dead_code)]
+#[derive(Debug, Default, Clone, PartialEq)]
 pub struct Block {
     /// Block header
     pub header: Header,
@@ -37,7 +88,136 @@ pub struct Block {
     pub uncles: Vec<Header>
 }
 
+/// A block with full transaction objects, returned by `eth_getBlockByNumber`.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FullBlock {
+    /// Block number, `None` for the pending block.
+    pub number: Option<U64>,
+    /// Block hash, `None` for the pending block.
+    pub hash: Option<H256>,
+    #[serde(rename = "parentHash")]
+    pub parent_hash: H256,
+    /// Proof-of-work nonce, `None` for the pending block.
+    pub nonce: Option<H64>,
+    #[serde(rename = "sha3Uncles")]
+    pub uncles_hash: H256,
+    /// Logs bloom, `None` for the pending block.
+    #[serde(rename = "logsBloom")]
+    pub logs_bloom: Option<Bloom>,
+    #[serde(rename = "transactionsRoot")]
+    pub transactions_root: H256,
+    #[serde(rename = "stateRoot")]
+    pub state_root: H256,
+    #[serde(rename = "receiptsRoot")]
+    pub receipts_root: H256,
+    /// Block author.
+    pub miner: H160,
+    pub difficulty: U256,
+    #[serde(rename = "totalDifficulty")]
+    pub total_difficulty: Option<U256>,
+    #[serde(rename = "extraData")]
+    pub extra_data: super::Bytes,
+    pub size: Option<U64>,
+    #[serde(rename = "gasLimit")]
+    pub gas_limit: U256,
+    #[serde(rename = "gasUsed")]
+    pub gas_used: U256,
+    pub timestamp: U64,
+    #[serde(rename = "mixHash")]
+    pub mix_hash: Option<H256>,
+    /// Base fee per gas, only present since London.
+    #[serde(rename = "baseFeePerGas")]
+    pub base_fee_per_gas: Option<U256>,
+    /// Withdrawals root, only present since Shanghai.
+    #[serde(rename = "withdrawalsRoot", skip_serializing_if = "Option::is_none")]
+    pub withdrawals_root: Option<H256>,
+    /// Blob gas used, only present since Cancun.
+    #[serde(rename = "blobGasUsed", skip_serializing_if = "Option::is_none")]
+    pub blob_gas_used: Option<U64>,
+    /// Excess blob gas, only present since Cancun.
+    #[serde(rename = "excessBlobGas", skip_serializing_if = "Option::is_none")]
+    pub excess_blob_gas: Option<U64>,
+    /// Parent beacon block root, only present since Cancun.
+    #[serde(rename = "parentBeaconBlockRoot", skip_serializing_if = "Option::is_none")]
+    pub parent_beacon_block_root: Option<H256>,
+    /// Execution requests hash, only present since Prague.
+    #[serde(rename = "requestsHash", skip_serializing_if = "Option::is_none")]
+    pub requests_hash: Option<H256>,
+    pub uncles: Vec<H256>,
+    pub transactions: Vec<FullTransaction>,
+}
+
+impl FullBlock {
+    /// Rebuild the header from the block fields, the seal is the mix hash and nonce,
+    /// followed by the fields added after London.
+    pub fn header(&self) -> Header {
+        let mut seal = vec![
+            rlp::encode(&self.mix_hash.unwrap_or_default()),
+            rlp::encode(&self.nonce.unwrap_or_default()),
+        ];
+        if let Some(base_fee) = self.base_fee_per_gas {
+            seal.push(rlp::encode(&base_fee));
+        }
+        if let Some(root) = self.withdrawals_root {
+            seal.push(rlp::encode(&root));
+        }
+        if let Some(gas) = self.blob_gas_used {
+            seal.push(rlp::encode(&gas));
+        }
+        if let Some(gas) = self.excess_blob_gas {
+            seal.push(rlp::encode(&gas));
+        }
+        if let Some(root) = self.parent_beacon_block_root {
+            seal.push(rlp::encode(&root));
+        }
+        if let Some(hash) = self.requests_hash {
+            seal.push(rlp::encode(&hash));
+        }
+
+        Header {
+            parent_hash: self.parent_hash,
+            timestamp: self.timestamp.as_u64(),
+            number: self.number.unwrap_or_default().as_u64(),
+            author: self.miner,
+            transactions_root: self.transactions_root,
+            uncles_hash: self.uncles_hash,
+            extra_data: self.extra_data.0.clone(),
+            state_root: self.state_root,
+            receipts_root: self.receipts_root,
+            log_bloom: self.logs_bloom.unwrap_or_default(),
+            gas_used: self.gas_used,
+            gas_limit: self.gas_limit,
+            difficulty: self.difficulty,
+            seal,
+            hash: None,
+        }
+    }
+}
+
+impl Encodable for Block {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(3);
+        s.append(&self.header);
+        s.append_list(&self.transactions);
+        s.append_list(&self.uncles);
+    }
+}
+
+impl Decodable for Block {
+    fn decode(r: &Rlp) -> Result<Self, DecoderError> {
+        if r.item_count()? != 3 {
+            return Err(DecoderError::RlpIncorrectListLen);
+        }
+        Ok(Block {
+            header: r.val_at(0)?,
+            transactions: r.list_at(1)?,
+            uncles: r.list_at(2)?,
+        })
+    }
+}
+
 /// A `UnverifiedTransaction` with successfully recovered `sender`.
+#[allow(dead_code)]
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct SignedTransaction {
     transaction: UnverifiedTransaction,
@@ -45,6 +225,50 @@ pub struct SignedTransaction {
     public: Option<Public>,
 }
 
+#[allow(dead_code)]
+impl SignedTransaction {
+    /// Recover the sender from the signature, `None` if the signature is invalid.
+    pub fn new(transaction: UnverifiedTransaction) -> Option<Self> {
+        let mut r = [0u8; 32];
+        let mut s = [0u8; 32];
+        transaction.r.to_big_endian(&mut r);
+        transaction.s.to_big_endian(&mut s);
+        let signature = EcdsaSignature(r, s, transaction.standard_v() as i8);
+        let message = keccak(transaction.raw_msg());
+        let public = crate::eth::ecdsa_recover(&signature, message.as_fixed_bytes())?;
+        Some(SignedTransaction {
+            sender: Address::from_slice(&keccak(&public[..])[12..]),
+            public: Some(Public::from(public)),
+            transaction,
+        })
+    }
+
+    pub fn transaction(&self) -> &UnverifiedTransaction {
+        &self.transaction
+    }
+
+    pub fn sender(&self) -> &Address {
+        &self.sender
+    }
+
+    pub fn public(&self) -> Option<&Public> {
+        self.public.as_ref()
+    }
+}
+
+impl Encodable for SignedTransaction {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        self.transaction.rlp_append(s);
+    }
+}
+
+impl Decodable for SignedTransaction {
+    fn decode(r: &Rlp) -> Result<Self, DecoderError> {
+        let transaction: UnverifiedTransaction = r.as_val()?;
+        SignedTransaction::new(transaction).ok_or(DecoderError::Custom("Invalid transaction signature"))
+    }
+}
+
 
 /// A block header.
 ///
@@ -52,7 +276,7 @@ pub struct SignedTransaction {
 /// which is non-specific.
 ///
 /// Doesn't do all that much on its own.
-#[derive(Debug, Clone, Eq)]
+#[derive(Debug, Default, Clone, Eq)]
 pub struct Header {
     /// Parent hash.
     parent_hash: H256,
@@ -92,7 +316,7 @@ pub struct Header {
 
 impl PartialEq for Header {
     fn eq(&self, c: &Header) -> bool {
-        if let (&Some(ref h1), &Some(ref h2)) = (&self.hash, &c.hash) {
+        if let (Some(h1), Some(h2)) = (&self.hash, &c.hash) {
             if h1 == h2 {
                 return true
             }
@@ -116,6 +340,93 @@ impl PartialEq for Header {
 }
 
 
+#[allow(dead_code)]
+impl Header {
+    pub fn parent_hash(&self) -> &H256 {
+        &self.parent_hash
+    }
+
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    pub fn number(&self) -> BlockNumber {
+        self.number
+    }
+
+    pub fn author(&self) -> &Address {
+        &self.author
+    }
+
+    pub fn transactions_root(&self) -> &H256 {
+        &self.transactions_root
+    }
+
+    pub fn uncles_hash(&self) -> &H256 {
+        &self.uncles_hash
+    }
+
+    pub fn extra_data(&self) -> &Bytes {
+        &self.extra_data
+    }
+
+    pub fn state_root(&self) -> &H256 {
+        &self.state_root
+    }
+
+    pub fn receipts_root(&self) -> &H256 {
+        &self.receipts_root
+    }
+
+    pub fn log_bloom(&self) -> &Bloom {
+        &self.log_bloom
+    }
+
+    pub fn gas_used(&self) -> &U256 {
+        &self.gas_used
+    }
+
+    pub fn gas_limit(&self) -> &U256 {
+        &self.gas_limit
+    }
+
+    pub fn difficulty(&self) -> &U256 {
+        &self.difficulty
+    }
+
+    /// The RLP encoded fields after the extra data, e.g. the mix hash and nonce.
+    pub fn seal(&self) -> &[Bytes] {
+        &self.seal
+    }
+
+    /// The memoized hash, or the keccak hash of the RLP encoding.
+    pub fn hash(&self) -> H256 {
+        self.hash.unwrap_or_else(|| keccak(rlp::encode(self)))
+    }
+}
+
+impl Encodable for Header {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(13 + self.seal.len());
+        s.append(&self.parent_hash);
+        s.append(&self.uncles_hash);
+        s.append(&self.author);
+        s.append(&self.state_root);
+        s.append(&self.transactions_root);
+        s.append(&self.receipts_root);
+        s.append(&self.log_bloom);
+        s.append(&self.difficulty);
+        s.append(&self.number);
+        s.append(&self.gas_limit);
+        s.append(&self.gas_used);
+        s.append(&self.timestamp);
+        s.append(&self.extra_data);
+        for item in &self.seal {
+            s.append_raw(item, 1);
+        }
+    }
+}
+
 impl Decodable for Header {
     fn decode(r: &Rlp) -> Result<Self, DecoderError> {
         let mut blockheader = Header {
@@ -143,3 +454,163 @@ impl Decodable for Header {
         Ok(blockheader)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The example of EIP-155, and the EIP-1559 tx signed by the same key 0x4646..46
+    const LEGACY_TX: &str = "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83";
+    const DYNAMIC_FEE_TX: &str = "02f872010784773594008506fc23ac0082ea6094353535353535353535353535353535353535353587038d7ea4c6800080c080a069a7d8e3c19721428f0e6fcee4b99c83669b929568b3821ab098bb03297b3b13a072494b609ba086289d1387f3ba8e6ab0d49a123df70ea21e7c9f2f2a0cb1c54b";
+
+    /// xorshift, deterministic pseudo random numbers for the round-trip tests.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn bytes(&mut self, len: usize) -> Vec<u8> {
+            (0..len).map(|_| self.next() as u8).collect()
+        }
+
+        fn h256(&mut self) -> H256 {
+            H256::from_slice(&self.bytes(32))
+        }
+
+        fn u256(&mut self) -> U256 {
+            // zero and small values have the shortest encodings
+            match self.next() % 3 {
+                0 => U256::zero(),
+                1 => U256::from(self.next() % 256),
+                _ => U256::from_big_endian(&self.bytes(32)),
+            }
+        }
+    }
+
+    fn random_header(rng: &mut Rng) -> Header {
+        let seal_len = (rng.next() % 9) as usize;
+        Header {
+            parent_hash: rng.h256(),
+            timestamp: rng.next() >> (rng.next() % 64),
+            number: rng.next() >> (rng.next() % 64),
+            author: H160::from_slice(&rng.bytes(20)),
+            transactions_root: rng.h256(),
+            uncles_hash: rng.h256(),
+            extra_data: {
+                let len = (rng.next() % 40) as usize;
+                rng.bytes(len)
+            },
+            state_root: rng.h256(),
+            receipts_root: rng.h256(),
+            log_bloom: Bloom::from_slice(&rng.bytes(BLOOM_SIZE)),
+            gas_used: rng.u256(),
+            gas_limit: rng.u256(),
+            difficulty: rng.u256(),
+            seal: (0..seal_len).map(|_| rlp::encode(&rng.h256())).collect(),
+            hash: None,
+        }
+    }
+
+    fn signed_tx(raw: &str) -> SignedTransaction {
+        let tx = UnverifiedTransaction::decode_raw(&hex::decode(raw).unwrap()).unwrap();
+        SignedTransaction::new(tx).unwrap()
+    }
+
+    #[test]
+    fn test_header_round_trip() {
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        for _ in 0..200 {
+            let header = random_header(&mut rng);
+            let encoded = rlp::encode(&header);
+            let decoded: Header = rlp::decode(&encoded).unwrap();
+            assert_eq!(decoded, header);
+            assert_eq!(decoded.hash(), keccak(&encoded));
+            assert_eq!(decoded.hash(), header.hash());
+            assert_eq!(rlp::encode(&decoded), encoded);
+            assert_eq!(decoded.seal(), header.seal());
+            assert_eq!(decoded.number(), header.number());
+        }
+    }
+
+    #[test]
+    fn test_signed_transaction() {
+        for raw in [LEGACY_TX, DYNAMIC_FEE_TX].iter() {
+            let tx = signed_tx(raw);
+            assert_eq!(format!("{:x}", tx.sender()), "9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f");
+            assert_eq!(keccak(&tx.public().unwrap()[..])[12..], tx.sender()[..]);
+            let decoded: SignedTransaction = rlp::decode(&rlp::encode(&tx)).unwrap();
+            assert_eq!(decoded, tx);
+        }
+
+        // the sender cannot be recovered
+        let mut invalid = signed_tx(LEGACY_TX).transaction().clone();
+        invalid.v = 1;
+        assert!(rlp::decode::<SignedTransaction>(&rlp::encode(&invalid)).is_err());
+    }
+
+    #[test]
+    fn test_block_round_trip() {
+        let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+        for i in 0..20 {
+            let transactions = (0..i % 4)
+                .map(|j| signed_tx(if j % 2 == 0 { LEGACY_TX } else { DYNAMIC_FEE_TX }))
+                .collect();
+            let uncles = (0..i % 3).map(|_| random_header(&mut rng)).collect();
+            let block = Block {
+                header: random_header(&mut rng),
+                transactions,
+                uncles,
+            };
+            let encoded = rlp::encode(&block);
+            let decoded: Block = rlp::decode(&encoded).unwrap();
+            assert_eq!(decoded, block);
+            assert_eq!(rlp::encode(&decoded), encoded);
+        }
+    }
+
+    #[test]
+    fn test_bloom() {
+        let address = hex::decode("ef2d6d194084c2de36e0dabfce45d046b37d1106").unwrap();
+        let topic = hex::decode("02c69be41d0b7e40352fc85be1cd65eb03d40ef8427a0ca4596b1ead9a00e9fc").unwrap();
+        let mut bloom = Bloom::zero();
+        bloom.accrue(&address);
+        bloom.accrue(&topic);
+        // The example of ethbloom
+        let expected = hex::decode(
+            "00000000000000000000000000000000\
+             00000000100000000000000000000000\
+             00000000000000000000000000000000\
+             00000000000000000000000000000000\
+             00000000000000000000000000000000\
+             00000000000000000000000000000000\
+             00000002020000000000000000000000\
+             00000000000000000000000800000000\
+             10000000000000000000000000000000\
+             00000000000000000000001000000000\
+             00000000000000000000000000000000\
+             00000000000000000000000000000000\
+             00000000000000000000000000000000\
+             00000000000000000000000000000000\
+             00000000000000000000000000000000\
+             00000000000000000000000000000000",
+        )
+        .unwrap();
+        assert_eq!(bloom, Bloom::from_slice(&expected));
+        assert!(bloom.contains_input(&address));
+        assert!(bloom.contains_input(&topic));
+        assert!(!bloom.contains_input(b"not added"));
+
+        let mut other = Bloom::zero();
+        other.accrue(&address);
+        assert!(bloom.contains_bloom(&other));
+        other.accrue(b"not added");
+        assert!(!bloom.contains_bloom(&other));
+        bloom.accrue_bloom(&other);
+        assert!(bloom.contains_bloom(&other));
+    }
+}
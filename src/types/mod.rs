@@ -1,9 +1,13 @@
 mod bytes;
 mod tx;
 mod block;
+mod receipt;
 
 pub use self::bytes::Bytes;
 pub use self::tx::{FullTransaction, EthereumAddress, EcdsaSignature, UnverifiedTransaction, keccak};
-pub use ethereum_types::{BigEndianHash, H160, H256, U128, U256, U64, H512};
+pub use ethereum_types::{H64, H160, H256, U256, U64, H512};
+#[allow(unused_imports)]
 pub use self::block::Block;
+pub use self::block::{Bloom, FullBlock, Header};
+pub use self::receipt::{Log, Receipt};
 
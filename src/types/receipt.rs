@@ -0,0 +1,84 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Bloom, Bytes, H160, H256, U256, U64};
+
+/// A log entry emitted by the transaction.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Log {
+    pub address: H160,
+    pub topics: Vec<H256>,
+    pub data: Bytes,
+    #[serde(rename = "logIndex")]
+    pub log_index: Option<U64>,
+}
+
+impl rlp::Encodable for Log {
+    fn rlp_append(&self, s: &mut rlp::RlpStream) {
+        s.begin_list(3);
+        s.append(&self.address);
+        s.append_list(&self.topics);
+        s.append(&self.data);
+    }
+}
+
+/// The receipt of a transaction, returned by `eth_getTransactionReceipt`.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Receipt {
+    #[serde(rename = "transactionHash")]
+    pub transaction_hash: H256,
+    #[serde(rename = "transactionIndex")]
+    pub transaction_index: U64,
+    #[serde(rename = "blockHash")]
+    pub block_hash: Option<H256>,
+    #[serde(rename = "blockNumber")]
+    pub block_number: Option<U64>,
+    #[serde(rename = "cumulativeGasUsed")]
+    pub cumulative_gas_used: U256,
+    #[serde(rename = "gasUsed")]
+    pub gas_used: U256,
+    /// The created contract address if the transaction is a contract creation.
+    #[serde(rename = "contractAddress")]
+    pub contract_address: Option<H160>,
+    /// `1` for success, `0` for failure, absent before Byzantium.
+    pub status: Option<U64>,
+    /// The post-transaction state root, only before Byzantium.
+    pub root: Option<H256>,
+    pub logs: Vec<Log>,
+    #[serde(rename = "logsBloom")]
+    pub logs_bloom: Bloom,
+    /// The type of the transaction, absent before Berlin.
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub tx_type: Option<U64>,
+}
+
+impl Receipt {
+    /// Whether the transaction failed or was reverted.
+    ///
+    /// The receipts before Byzantium have no status, they are never considered as failed.
+    pub fn is_failed(&self) -> bool {
+        self.status.is_some_and(|status| status.is_zero())
+    }
+
+    /// The consensus encoding of the receipt, the leaf of the receipts trie.
+    ///
+    /// The receipt of the typed transaction is prefixed by the transaction type.
+    pub fn encoded(&self) -> Vec<u8> {
+        let mut s = rlp::RlpStream::new_list(4);
+        match (self.status, self.root) {
+            (Some(status), _) => s.append(&status.as_u64()),
+            (None, Some(root)) => s.append(&root),
+            (None, None) => s.append_empty_data(),
+        };
+        s.append(&self.cumulative_gas_used);
+        s.append(&self.logs_bloom);
+        s.append_list(&self.logs);
+        match self.tx_type.map(|tx_type| tx_type.as_u64()) {
+            Some(tx_type) if tx_type > 0 => {
+                let mut encoded = vec![tx_type as u8];
+                encoded.extend(s.out());
+                encoded
+            }
+            _ => s.out(),
+        }
+    }
+}
@@ -6,25 +6,19 @@ use parity_codec::{Decode, Encode};
 
 pub type EthereumAddress = [u8; 20];
 
-#[derive(Encode, Decode, Clone, PartialEq)]
-#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Debug, Clone, PartialEq)]
 pub struct EcdsaSignature(pub [u8; 32], pub [u8; 32], pub i8);
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub enum Action {
     ///create creates new contract
+    #[default]
     Create,
     ///calls contract at given address
     /// in the case of a transfer, this is receiver's address.
     Call(H160),
 }
 
-impl Default for Action {
-    fn default() -> Action {
-        Action::Create
-    }
-}
-
 impl rlp::Encodable for Action {
     fn rlp_append(&self, s: &mut rlp::RlpStream) {
         match *self {
@@ -44,8 +38,112 @@ impl rlp::Decodable for Action {
     }
 }
 
+/// An entry of the EIP-2930 access list.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccessListItem {
+    pub address: H160,
+    #[serde(rename = "storageKeys")]
+    pub storage_keys: Vec<H256>,
+}
+
+pub type AccessList = Vec<AccessListItem>;
+
+/// An entry of the EIP-7702 authorization list.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Authorization {
+    #[serde(rename = "chainId")]
+    pub chain_id: U256,
+    pub address: H160,
+    pub nonce: U64,
+    #[serde(rename = "yParity")]
+    pub y_parity: U64,
+    pub r: U256,
+    pub s: U256,
+}
+
+impl rlp::Encodable for Authorization {
+    fn rlp_append(&self, s: &mut rlp::RlpStream) {
+        s.begin_list(6);
+        s.append(&self.chain_id);
+        s.append(&self.address);
+        s.append(&self.nonce.as_u64());
+        s.append(&self.y_parity.as_u64());
+        s.append(&self.r);
+        s.append(&self.s);
+    }
+}
+
+impl rlp::Decodable for Authorization {
+    fn decode(rlp: &rlp::Rlp) -> Result<Self, rlp::DecoderError> {
+        if rlp.item_count()? != 6 {
+            return Err(rlp::DecoderError::RlpIncorrectListLen);
+        }
+        Ok(Authorization {
+            chain_id: rlp.val_at(0)?,
+            address: rlp.val_at(1)?,
+            nonce: rlp.val_at::<u64>(2)?.into(),
+            y_parity: rlp.val_at::<u64>(3)?.into(),
+            r: rlp.val_at(4)?,
+            s: rlp.val_at(5)?,
+        })
+    }
+}
+
+impl rlp::Encodable for AccessListItem {
+    fn rlp_append(&self, s: &mut rlp::RlpStream) {
+        s.begin_list(2);
+        s.append(&self.address);
+        s.append_list(&self.storage_keys);
+    }
+}
+
+impl rlp::Decodable for AccessListItem {
+    fn decode(rlp: &rlp::Rlp) -> Result<Self, rlp::DecoderError> {
+        if rlp.item_count()? != 2 {
+            return Err(rlp::DecoderError::RlpIncorrectListLen);
+        }
+        Ok(AccessListItem {
+            address: rlp.val_at(0)?,
+            storage_keys: rlp.list_at(1)?,
+        })
+    }
+}
+
+/// EIP-2718 transaction type.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TxType {
+    ///legacy transaction, including EIP-155 replay protected transaction
+    #[default]
+    Legacy = 0,
+    ///EIP-2930 transaction with access list
+    AccessList = 1,
+    ///EIP-1559 transaction with dynamic fee
+    DynamicFee = 2,
+    ///EIP-4844 transaction carrying blobs
+    Blob = 3,
+    ///EIP-7702 transaction setting the code of the signers
+    SetCode = 4,
+}
+
+impl TxType {
+    pub fn from_u64(n: u64) -> Option<TxType> {
+        match n {
+            0 => Some(TxType::Legacy),
+            1 => Some(TxType::AccessList),
+            2 => Some(TxType::DynamicFee),
+            3 => Some(TxType::Blob),
+            4 => Some(TxType::SetCode),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Eq)]
 pub struct Transaction {
+    ///EIP-2718 transaction type
+    pub tx_type: TxType,
+    ///Chain ID of the typed transaction, legacy transaction keeps it in 'v'
+    pub chain_id: Option<u64>,
     ///Nonce
     pub nonce: U256,
     ///Gas price
@@ -58,6 +156,18 @@ pub struct Transaction {
     pub value: U256,
     ///Transaction data
     pub data: Bytes,
+    ///Access list of the typed transaction
+    pub access_list: AccessList,
+    ///Max fee per gas of the EIP-1559 transaction
+    pub max_fee_per_gas: U256,
+    ///Max priority fee per gas of the EIP-1559 transaction
+    pub max_priority_fee_per_gas: U256,
+    ///Max fee per blob gas of the EIP-4844 transaction
+    pub max_fee_per_blob_gas: U256,
+    ///Versioned hashes of the blobs of the EIP-4844 transaction
+    pub blob_versioned_hashes: Vec<H256>,
+    ///Authorization list of the EIP-7702 transaction
+    pub authorization_list: Vec<Authorization>,
 }
 
 impl Transaction {
@@ -76,10 +186,55 @@ impl Transaction {
         }
     }
 
+    ///the number of fields of the unsigned typed transaction
+    fn typed_item_count(&self) -> usize {
+        match self.tx_type {
+            TxType::Legacy | TxType::AccessList => 8,
+            TxType::DynamicFee => 9,
+            TxType::Blob => 11,
+            TxType::SetCode => 10,
+        }
+    }
+
+    ///append the fields of the unsigned typed transaction, the list must be began by caller
+    fn rlp_append_typed_fields(&self, s: &mut rlp::RlpStream) {
+        s.append(&self.chain_id.unwrap_or_default());
+        s.append(&self.nonce);
+        if self.tx_type == TxType::AccessList {
+            s.append(&self.gas_price);
+        } else {
+            s.append(&self.max_priority_fee_per_gas);
+            s.append(&self.max_fee_per_gas);
+        }
+        s.append(&self.gas);
+        s.append(&self.action);
+        s.append(&self.value);
+        s.append(&self.data);
+        s.append_list(&self.access_list);
+        match self.tx_type {
+            TxType::Blob => {
+                s.append(&self.max_fee_per_blob_gas);
+                s.append_list(&self.blob_versioned_hashes);
+            }
+            TxType::SetCode => {
+                s.append_list(&self.authorization_list);
+            }
+            _ => {}
+        }
+    }
+
+    ///the signing payload, the chain ID of typed transaction is taken from the transaction itself
     pub fn raw_msg(&self, chain_id: Option<u64>) -> Vec<u8> {
         let mut stream = rlp::RlpStream::new();
-        self.rlp_append_usigned_transaction(&mut stream, chain_id);
-        stream.out()
+        if self.tx_type == TxType::Legacy {
+            self.rlp_append_usigned_transaction(&mut stream, chain_id);
+            return stream.out();
+        }
+        stream.begin_list(self.typed_item_count());
+        self.rlp_append_typed_fields(&mut stream);
+        let mut raw = vec![self.tx_type as u8];
+        raw.extend(stream.out());
+        raw
     }
 }
 
@@ -102,12 +257,19 @@ impl ops::Deref for UnverifiedTransaction {
 
 impl rlp::Encodable for UnverifiedTransaction {
     fn rlp_append(&self, s: &mut rlp::RlpStream) {
-        self.rlp_append_sealed_transaction(s)
+        match self.tx_type {
+            TxType::Legacy => self.rlp_append_sealed_transaction(s),
+            // typed transaction is wrapped in a RLP string
+            _ => s.encoder().encode_value(&self.encoded()),
+        }
     }
 }
 
 impl rlp::Decodable for UnverifiedTransaction {
     fn decode(d: &rlp::Rlp) -> Result<Self, rlp::DecoderError> {
+        if !d.is_list() {
+            return d.decoder().decode_value(UnverifiedTransaction::decode_typed);
+        }
         if d.item_count()? != 9 {
             return Err(rlp::DecoderError::RlpIncorrectListLen);
         }
@@ -120,6 +282,7 @@ impl rlp::Decodable for UnverifiedTransaction {
                 action: d.val_at(3)?,
                 value: d.val_at(4)?,
                 data: d.val_at(5)?,
+                ..Default::default()
             },
             v: d.val_at(6)?,
             r: d.val_at(7)?,
@@ -135,7 +298,88 @@ impl UnverifiedTransaction {
         self.r.is_zero() && self.s.is_zero()
     }
 
+    ///decode the signed transaction, which is either a legacy RLP list or an EIP-2718 typed envelope
+    pub fn decode_raw(raw: &[u8]) -> Result<Self, rlp::DecoderError> {
+        match raw.first() {
+            None => Err(rlp::DecoderError::RlpIsTooShort),
+            Some(&first) if first >= 0xc0 => rlp::decode(raw),
+            Some(_) => Self::decode_typed(raw),
+        }
+    }
+
+    ///decode the EIP-2718 typed envelope: `type || rlp(fields)`
+    fn decode_typed(raw: &[u8]) -> Result<Self, rlp::DecoderError> {
+        let tx_type = match raw.first().and_then(|t| TxType::from_u64(u64::from(*t))) {
+            Some(TxType::Legacy) | None => return Err(rlp::DecoderError::Custom("Unknown transaction type")),
+            Some(tx_type) => tx_type,
+        };
+        let d = rlp::Rlp::new(&raw[1..]);
+        let mut unsigned = Transaction {
+            tx_type,
+            ..Default::default()
+        };
+        if d.item_count()? != unsigned.typed_item_count() + 3 {
+            return Err(rlp::DecoderError::RlpIncorrectListLen);
+        }
+        unsigned.chain_id = Some(d.val_at(0)?);
+        unsigned.nonce = d.val_at(1)?;
+        let mut i = 2;
+        if tx_type == TxType::AccessList {
+            unsigned.gas_price = d.val_at(2)?;
+            i += 1;
+        } else {
+            unsigned.max_priority_fee_per_gas = d.val_at(2)?;
+            unsigned.max_fee_per_gas = d.val_at(3)?;
+            i += 2;
+        }
+        unsigned.gas = d.val_at(i)?;
+        unsigned.action = d.val_at(i + 1)?;
+        unsigned.value = d.val_at(i + 2)?;
+        unsigned.data = d.val_at(i + 3)?;
+        unsigned.access_list = d.list_at(i + 4)?;
+        i += 5;
+        match tx_type {
+            TxType::Blob => {
+                unsigned.max_fee_per_blob_gas = d.val_at(i)?;
+                unsigned.blob_versioned_hashes = d.list_at(i + 1)?;
+                i += 2;
+            }
+            TxType::SetCode => {
+                unsigned.authorization_list = d.list_at(i)?;
+                i += 1;
+            }
+            _ => {}
+        }
+        Ok(UnverifiedTransaction {
+            unsigned,
+            v: d.val_at(i)?,
+            r: d.val_at(i + 1)?,
+            s: d.val_at(i + 2)?,
+            hash: keccak(raw),
+        })
+    }
+
+    ///the signed transaction bytes, as broadcast to the network and stored in the transaction trie
+    pub fn encoded(&self) -> Vec<u8> {
+        let mut stream = rlp::RlpStream::new();
+        self.rlp_append_sealed_transaction(&mut stream);
+        if self.tx_type == TxType::Legacy {
+            return stream.out();
+        }
+        let mut raw = vec![self.tx_type as u8];
+        raw.extend(stream.out());
+        raw
+    }
+
     fn rlp_append_sealed_transaction(&self, s: &mut rlp::RlpStream) {
+        if self.tx_type != TxType::Legacy {
+            s.begin_list(self.typed_item_count() + 3);
+            self.rlp_append_typed_fields(s);
+            s.append(&self.v);
+            s.append(&self.r);
+            s.append(&self.s);
+            return;
+        }
         s.begin_list(9);
         s.append(&self.nonce);
         s.append(&self.gas_price);
@@ -150,11 +394,19 @@ impl UnverifiedTransaction {
 
     ///returns standardized 'v' value (0, 1 or 4 (invalid))
     pub fn standard_v(&self) -> u8 {
-        check_replay_protection(self.v)
+        match self.tx_type {
+            TxType::Legacy => check_replay_protection(self.v),
+            // typed transaction keeps the y parity in 'v'
+            _ if self.v <= 1 => self.v as u8,
+            _ => 4,
+        }
     }
 
     ///the chain ID, or 'None' if this is a gloable transaction
     pub fn chain_id(&self) -> Option<u64> {
+        if self.tx_type != TxType::Legacy {
+            return self.unsigned.chain_id;
+        }
         match self.v {
             v if self.is_unsigned() => Some(v),
             v if v >= 35 => Some((v - 35) / 2),
@@ -167,14 +419,19 @@ impl UnverifiedTransaction {
     }
 }
 
+/// The transaction returned by `eth_getTransactionByHash` and `eth_getBlockByNumber`.
+///
+/// The block fields are `null` for the pending transaction, and the signature fields
+/// may be absent for the unsigned transaction returned by some nodes.
 #[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FullTransaction {
     #[serde(rename = "blockHash")]
-    pub block_hash: H256,
+    pub block_hash: Option<H256>,
     #[serde(rename = "blockNumber")]
-    pub block_number: H256,
+    pub block_number: Option<U64>,
     pub from: H160,
-    pub to: H160,
+    /// `None` for the contract creation transaction.
+    pub to: Option<H160>,
     pub gas: U256,
     #[serde(rename = "gasPrice")]
     pub gas_price: U256,
@@ -182,28 +439,74 @@ pub struct FullTransaction {
     pub nonce: U256,
     pub raw: Option<Bytes>,
     pub input: Bytes,
-    pub r: U256,
-    pub s: U256,
-    pub v: U64,
+    pub r: Option<U256>,
+    pub s: Option<U256>,
+    pub v: Option<U64>,
+    /// The parity of the signature of the typed transaction, the same as `v`.
+    #[serde(rename = "yParity", skip_serializing_if = "Option::is_none")]
+    pub y_parity: Option<U64>,
     #[serde(rename = "transactionIndex")]
-    pub transaction_index: U128,
+    pub transaction_index: Option<U64>,
     pub value: U256,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub tx_type: Option<U64>,
+    #[serde(rename = "chainId", skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<U64>,
+    #[serde(rename = "accessList", skip_serializing_if = "Option::is_none")]
+    pub access_list: Option<AccessList>,
+    #[serde(rename = "maxFeePerGas", skip_serializing_if = "Option::is_none")]
+    pub max_fee_per_gas: Option<U256>,
+    #[serde(rename = "maxPriorityFeePerGas", skip_serializing_if = "Option::is_none")]
+    pub max_priority_fee_per_gas: Option<U256>,
+    #[serde(rename = "maxFeePerBlobGas", skip_serializing_if = "Option::is_none")]
+    pub max_fee_per_blob_gas: Option<U256>,
+    #[serde(rename = "blobVersionedHashes", skip_serializing_if = "Option::is_none")]
+    pub blob_versioned_hashes: Option<Vec<H256>>,
+    #[serde(rename = "authorizationList", skip_serializing_if = "Option::is_none")]
+    pub authorization_list: Option<Vec<Authorization>>,
+}
+
+impl FullTransaction {
+    /// Whether the transaction has the complete signature.
+    pub fn is_signed(&self) -> bool {
+        self.r.is_some() && self.s.is_some() && (self.v.is_some() || self.y_parity.is_some())
+    }
+
+    /// Whether the type is known to `TxType`, so that the transaction can be encoded from its fields.
+    pub fn is_known_type(&self) -> bool {
+        match self.tx_type {
+            Some(tx_type) => TxType::from_u64(tx_type.as_u64()).is_some(),
+            None => true,
+        }
+    }
 }
 
 impl From<FullTransaction> for UnverifiedTransaction {
     fn from(tx: FullTransaction) -> UnverifiedTransaction {
+        let tx_type = tx
+            .tx_type
+            .and_then(|tx_type| TxType::from_u64(tx_type.as_u64()))
+            .unwrap_or_default();
         UnverifiedTransaction {
             unsigned: Transaction {
+                tx_type,
+                chain_id: tx.chain_id.map(|chain_id| chain_id.as_u64()),
                 nonce: tx.nonce,
                 gas_price: tx.gas_price,
                 gas: tx.gas,
-                action: Action::Call(tx.to),
+                action: tx.to.map(Action::Call).unwrap_or(Action::Create),
                 value: tx.value,
                 data: tx.input,
+                access_list: tx.access_list.unwrap_or_default(),
+                max_fee_per_gas: tx.max_fee_per_gas.unwrap_or_default(),
+                max_priority_fee_per_gas: tx.max_priority_fee_per_gas.unwrap_or_default(),
+                max_fee_per_blob_gas: tx.max_fee_per_blob_gas.unwrap_or_default(),
+                blob_versioned_hashes: tx.blob_versioned_hashes.unwrap_or_default(),
+                authorization_list: tx.authorization_list.unwrap_or_default(),
             },
-            v: tx.v.as_u64(),
-            r: tx.r,
-            s: tx.s,
+            v: tx.v.or(tx.y_parity).map(|v| v.as_u64()).unwrap_or_default(),
+            r: tx.r.unwrap_or_default(),
+            s: tx.s.unwrap_or_default(),
             hash: tx.hash,
         }
     }
@@ -211,8 +514,8 @@ impl From<FullTransaction> for UnverifiedTransaction {
 
 fn check_replay_protection(v: u64) -> u8 {
     match v {
-        v if v == 27 => 0,
-        v if v == 28 => 1,
+        27 => 0,
+        28 => 1,
         v if v >= 35 => ((v - 1) % 2) as u8,
         _ => 4,
     }
@@ -222,3 +525,260 @@ pub fn keccak<T: AsRef<[u8]>>(s: T) -> H256 {
     let result = tiny_keccak::keccak256(s.as_ref());
     H256::from(result)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The example of EIP-155, signed by the key 0x4646..46 whose address is 0x9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f
+    const LEGACY_TX: &str = "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83";
+    // Signed by the same key
+    const ACCESS_LIST_TX: &str = "01f8a601078504a817c80082ea6094353535353535353535353535353535353535353587038d7ea4c6800080f838f7941111111111111111111111111111111111111111e1a0222222222222222222222222222222222222222222222222222222222222222201a05ced7a47dd7e7e9e088c918f22582304f6239acc6553b058cccd6d2d7f5ed5faa07484080398b9d99c8136a3a377a10e285e1b1ec08e6b844080e60425b46431e5";
+    const DYNAMIC_FEE_TX: &str = "02f872010784773594008506fc23ac0082ea6094353535353535353535353535353535353535353587038d7ea4c6800080c080a069a7d8e3c19721428f0e6fcee4b99c83669b929568b3821ab098bb03297b3b13a072494b609ba086289d1387f3ba8e6ab0d49a123df70ea21e7c9f2f2a0cb1c54b";
+    // The transactions of the fixture block #1, signed by the same key
+    const BLOB_TX: &str = "03f8920108843b9aca008506fc23ac008252089435353535353535353535353535353535353535358080c084b2d05e00e1a001a915e4d060149eb4365960e6a7a45f334393093061116b197e3240065ff2d880a0af1f756d6986fdbf0fa707459ae3b31e6a36c1cbde879b37dbe948142712b186a0083a6b4ba5badb5fe9f38029d3161d3fc62e2acc533502bb7697295fd26f969e";
+    const SET_CODE_TX: &str = "04f8c90109843b9aca008506fc23ac0082ea60949d8a62f656a8d1615c1294fd71e9cfb3e4855a4f8080c0f85cf85a019442424242424242424242424242424242424242420a01a0469539c3ffaed3018e6bd98328f06fb567b4c143e224a54e971cdbd1cc2fe970a0595db889971ae593f1cc783c6aec7407034882e5bbb4560660652a32f9c3232e01a07513dd33091eaa484ae80535a16a6e2b63922a56f3097d5a9c197e8939de825aa019a9f594f1acc326033dbe1b50e937e68a1371d50881dd5bd0ad72fdd4b9c283";
+
+    #[test]
+    fn test_legacy_transaction() {
+        let raw = hex::decode(LEGACY_TX).unwrap();
+        let tx = UnverifiedTransaction::decode_raw(&raw).unwrap();
+        assert_eq!(tx.tx_type, TxType::Legacy);
+        assert_eq!(tx.chain_id(), Some(1));
+        assert_eq!(tx.standard_v(), 0);
+        assert_eq!(
+            hex::encode(tx.raw_msg()),
+            "ec098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a764000080018080"
+        );
+        assert_eq!(
+            format!("{:x}", tx.hash),
+            "33469b22e9f636356c4160a87eb19df52b7412e8eac32a4a55ffe88ea8350788"
+        );
+        assert_eq!(tx.encoded(), raw);
+        assert_eq!(rlp::encode(&tx), raw);
+    }
+
+    #[test]
+    fn test_access_list_transaction() {
+        let raw = hex::decode(ACCESS_LIST_TX).unwrap();
+        let tx = UnverifiedTransaction::decode_raw(&raw).unwrap();
+        assert_eq!(tx.tx_type, TxType::AccessList);
+        assert_eq!(tx.chain_id(), Some(1));
+        assert_eq!(tx.standard_v(), 1);
+        assert_eq!(tx.access_list.len(), 1);
+        assert_eq!(tx.access_list[0].address, H160::repeat_byte(0x11));
+        assert_eq!(tx.access_list[0].storage_keys, vec![H256::repeat_byte(0x22)]);
+        assert_eq!(
+            hex::encode(tx.raw_msg()),
+            "01f86301078504a817c80082ea6094353535353535353535353535353535353535353587038d7ea4c6800080f838f7941111111111111111111111111111111111111111e1a02222222222222222222222222222222222222222222222222222222222222222"
+        );
+        assert_eq!(tx.hash, keccak(&raw));
+        assert_eq!(tx.encoded(), raw);
+    }
+
+    #[test]
+    fn test_dynamic_fee_transaction() {
+        let raw = hex::decode(DYNAMIC_FEE_TX).unwrap();
+        let tx = UnverifiedTransaction::decode_raw(&raw).unwrap();
+        assert_eq!(tx.tx_type, TxType::DynamicFee);
+        assert_eq!(tx.chain_id(), Some(1));
+        assert_eq!(tx.standard_v(), 0);
+        assert_eq!(tx.max_priority_fee_per_gas, U256::from(2_000_000_000u64));
+        assert_eq!(tx.max_fee_per_gas, U256::from(30_000_000_000u64));
+        assert_eq!(
+            hex::encode(tx.raw_msg()),
+            "02ef010784773594008506fc23ac0082ea6094353535353535353535353535353535353535353587038d7ea4c6800080c0"
+        );
+        assert_eq!(
+            format!("{:x}", tx.hash),
+            "f2a3071f755aa14e2699f45ebf7754ddc7fed908e3e55e859a7667c6d02ab356"
+        );
+        assert_eq!(tx.encoded(), raw);
+
+        // typed transaction is wrapped in a RLP string inside the block
+        let wrapped = rlp::encode(&tx);
+        assert_eq!(rlp::decode::<UnverifiedTransaction>(&wrapped).unwrap(), tx);
+    }
+
+    #[test]
+    fn test_blob_transaction() {
+        let raw = hex::decode(BLOB_TX).unwrap();
+        let tx = UnverifiedTransaction::decode_raw(&raw).unwrap();
+        assert_eq!(tx.tx_type, TxType::Blob);
+        assert_eq!(tx.chain_id(), Some(1));
+        assert_eq!(tx.standard_v(), 0);
+        assert_eq!(tx.max_fee_per_blob_gas, U256::from(3_000_000_000u64));
+        assert_eq!(tx.blob_versioned_hashes.len(), 1);
+        assert_eq!(tx.blob_versioned_hashes[0].as_bytes()[0], 0x01);
+        assert_eq!(tx.hash, keccak(&raw));
+        assert_eq!(tx.encoded(), raw);
+        // the signing payload drops the signature fields
+        assert_eq!(&tx.raw_msg()[..5], &hex::decode("03f84f0108").unwrap()[..]);
+    }
+
+    #[test]
+    fn test_set_code_transaction() {
+        let raw = hex::decode(SET_CODE_TX).unwrap();
+        let tx = UnverifiedTransaction::decode_raw(&raw).unwrap();
+        assert_eq!(tx.tx_type, TxType::SetCode);
+        assert_eq!(tx.standard_v(), 1);
+        assert_eq!(tx.authorization_list.len(), 1);
+        let authorization = &tx.authorization_list[0];
+        assert_eq!(authorization.chain_id, U256::from(1));
+        assert_eq!(authorization.address, H160::repeat_byte(0x42));
+        assert_eq!(authorization.nonce, U64::from(10));
+        assert_eq!(tx.hash, keccak(&raw));
+        assert_eq!(tx.encoded(), raw);
+        assert!(UnverifiedTransaction::decode_raw(&[0x05, 0xc0]).is_err());
+    }
+
+    #[test]
+    fn test_typed_transactions_in_list() {
+        let txs = [LEGACY_TX, ACCESS_LIST_TX, DYNAMIC_FEE_TX, BLOB_TX, SET_CODE_TX]
+            .iter()
+            .map(|raw| UnverifiedTransaction::decode_raw(&hex::decode(raw).unwrap()).unwrap())
+            .collect::<Vec<_>>();
+        // each typed transaction is one item of the list, like the transactions of the block
+        let encoded = rlp::encode_list(&txs);
+        let list = rlp::Rlp::new(&encoded);
+        assert_eq!(list.payload_info().unwrap().total(), encoded.len());
+        assert_eq!(list.item_count().unwrap(), txs.len());
+        assert_eq!(rlp::decode_list::<UnverifiedTransaction>(&encoded), txs);
+    }
+
+    /// Parse the JSON of the node, and check the serialized transaction is parsed back to the same.
+    fn parse_full_tx(json: &str) -> FullTransaction {
+        let tx: FullTransaction = serde_json::from_str(json).unwrap();
+        let serialized = serde_json::to_string(&tx).unwrap();
+        assert_eq!(serde_json::from_str::<FullTransaction>(&serialized).unwrap(), tx);
+        tx
+    }
+
+    #[test]
+    fn test_geth_legacy_full_tx() {
+        let tx = parse_full_tx(
+            r#"{
+                "blockHash": "0x8e38b4dbf6b11fcc3b9dee84fb7986e29ca0a02cecd8977c161ff7333329681e",
+                "blockNumber": "0xf4240",
+                "from": "0x9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f",
+                "gas": "0x5208",
+                "gasPrice": "0x4a817c800",
+                "hash": "0x33469b22e9f636356c4160a87eb19df52b7412e8eac32a4a55ffe88ea8350788",
+                "input": "0x",
+                "nonce": "0x9",
+                "to": "0x3535353535353535353535353535353535353535",
+                "transactionIndex": "0x1a",
+                "value": "0xde0b6b3a7640000",
+                "type": "0x0",
+                "chainId": "0x1",
+                "v": "0x25",
+                "r": "0x28ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276",
+                "s": "0x67cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83"
+            }"#,
+        );
+        assert_eq!(tx.block_number, Some(1_000_000.into()));
+        assert_eq!(tx.transaction_index, Some(26.into()));
+        assert!(tx.is_signed());
+
+        let unverified: UnverifiedTransaction = tx.clone().into();
+        assert_eq!(unverified.encoded(), hex::decode(LEGACY_TX).unwrap());
+        assert_eq!(keccak(unverified.encoded()), tx.hash);
+    }
+
+    #[test]
+    fn test_geth_dynamic_fee_full_tx() {
+        let tx = parse_full_tx(
+            r#"{
+                "accessList": [],
+                "blockHash": "0x8e38b4dbf6b11fcc3b9dee84fb7986e29ca0a02cecd8977c161ff7333329681e",
+                "blockNumber": "0xf4240",
+                "chainId": "0x1",
+                "from": "0x9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f",
+                "gas": "0xea60",
+                "gasPrice": "0x6fc23ac00",
+                "hash": "0xf2a3071f755aa14e2699f45ebf7754ddc7fed908e3e55e859a7667c6d02ab356",
+                "input": "0x",
+                "maxFeePerGas": "0x6fc23ac00",
+                "maxPriorityFeePerGas": "0x77359400",
+                "nonce": "0x7",
+                "to": "0x3535353535353535353535353535353535353535",
+                "transactionIndex": "0x0",
+                "value": "0x38d7ea4c68000",
+                "type": "0x2",
+                "v": "0x0",
+                "r": "0x69a7d8e3c19721428f0e6fcee4b99c83669b929568b3821ab098bb03297b3b13",
+                "s": "0x72494b609ba086289d1387f3ba8e6ab0d49a123df70ea21e7c9f2f2a0cb1c54b",
+                "yParity": "0x0"
+            }"#,
+        );
+        assert_eq!(tx.transaction_index, Some(0.into()));
+        assert_eq!(tx.access_list, Some(vec![]));
+
+        let unverified: UnverifiedTransaction = tx.clone().into();
+        assert_eq!(unverified.tx_type, TxType::DynamicFee);
+        assert_eq!(unverified.encoded(), hex::decode(DYNAMIC_FEE_TX).unwrap());
+        assert_eq!(keccak(unverified.encoded()), tx.hash);
+
+        // some nodes only return `yParity` for the typed transaction
+        let tx = FullTransaction { v: None, ..tx };
+        assert!(tx.is_signed());
+        assert_eq!(UnverifiedTransaction::from(tx).encoded(), hex::decode(DYNAMIC_FEE_TX).unwrap());
+    }
+
+    #[test]
+    fn test_parity_pending_full_tx() {
+        let tx = parse_full_tx(
+            r#"{
+                "blockHash": null,
+                "blockNumber": null,
+                "chainId": "0x1",
+                "condition": null,
+                "creates": null,
+                "from": "0x9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f",
+                "gas": "0x5208",
+                "gasPrice": "0x4a817c800",
+                "hash": "0x33469b22e9f636356c4160a87eb19df52b7412e8eac32a4a55ffe88ea8350788",
+                "input": "0x",
+                "nonce": "0x9",
+                "publicKey": "0x4bc2a31265153f07e70e0bab08724e6b85e217f8cd628ceb62974247bb493382ce28cab79ad7119ee1ad3ebcdb98a16805211530ecc6cfefa1b88e6dff99232a",
+                "r": "0x28ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276",
+                "raw": "0xf86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83",
+                "s": "0x67cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83",
+                "standardV": "0x0",
+                "to": "0x3535353535353535353535353535353535353535",
+                "transactionIndex": null,
+                "v": "0x25",
+                "value": "0xde0b6b3a7640000"
+            }"#,
+        );
+        assert_eq!(tx.block_hash, None);
+        assert_eq!(tx.block_number, None);
+        assert_eq!(tx.transaction_index, None);
+        assert_eq!(tx.tx_type, None);
+        assert_eq!(tx.raw.as_ref().map(|raw| raw.0.clone()), Some(hex::decode(LEGACY_TX).unwrap()));
+        assert_eq!(UnverifiedTransaction::from(tx).encoded(), hex::decode(LEGACY_TX).unwrap());
+    }
+
+    #[test]
+    fn test_unsigned_full_tx() {
+        // the pending transaction of the dev node without the signature fields
+        let tx = parse_full_tx(
+            r#"{
+                "blockHash": null,
+                "blockNumber": null,
+                "from": "0x9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f",
+                "gas": "0x5208",
+                "gasPrice": "0x4a817c800",
+                "hash": "0x0000000000000000000000000000000000000000000000000000000000000000",
+                "input": "0x",
+                "nonce": "0x0",
+                "to": null,
+                "transactionIndex": null,
+                "value": "0x0"
+            }"#,
+        );
+        assert_eq!(tx.to, None);
+        assert_eq!((tx.r, tx.s, tx.v), (None, None, None));
+        assert!(!tx.is_signed());
+    }
+}
@@ -0,0 +1,496 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::source::TxSource;
+use crate::trie::{ordered_trie_root, verify_index_proof, Trie};
+use crate::types::{keccak, Bytes, FullBlock, FullTransaction, Header, Receipt, UnverifiedTransaction, H256};
+
+/// The Merkle-Patricia proof of the transaction, or the receipt, at `index` of the block.
+///
+/// The RLP encoded header is included, so that the proof can be checked offline against the block hash.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct InclusionProof {
+    pub header: Bytes,
+    pub index: u64,
+    pub nodes: Vec<Bytes>,
+}
+
+impl InclusionProof {
+    /// Check the header hash, and return the value proven against the root selected from the header.
+    fn verify<F: Fn(&Header) -> H256>(&self, block_hash: &H256, root: F) -> Result<Vec<u8>> {
+        if keccak(&self.header.0) != *block_hash {
+            warn!("Header of the proof does not match block {:?}", block_hash);
+            return Err(Error::InvalidEthProof);
+        }
+        let header: Header = rlp::decode(&self.header.0)?;
+        let nodes = self.nodes.iter().map(|node| node.0.clone()).collect::<Vec<_>>();
+        match verify_index_proof(&root(&header), self.index as usize, &nodes) {
+            Some(value) => Ok(value),
+            None => Err(Error::InvalidEthProof),
+        }
+    }
+
+    /// Check the transaction `tx_hash` is included in the block `block_hash`.
+    pub fn verify_tx(&self, block_hash: &H256, tx_hash: &H256) -> Result<()> {
+        let value = self.verify(block_hash, |header| *header.transactions_root())?;
+        if keccak(value) != *tx_hash {
+            warn!("Proof of block {:?} is not for tx {:?}", block_hash, tx_hash);
+            return Err(Error::InvalidEthProof);
+        }
+        Ok(())
+    }
+
+    /// Check the receipt is included in the block `block_hash`.
+    pub fn verify_receipt(&self, block_hash: &H256, receipt: &Receipt) -> Result<()> {
+        let value = self.verify(block_hash, |header| *header.receipts_root())?;
+        if value != receipt.encoded() {
+            warn!("Proof of block {:?} is not for the receipt of tx {:?}", block_hash, receipt.transaction_hash);
+            return Err(Error::InvalidEthProof);
+        }
+        Ok(())
+    }
+}
+
+fn prove(block: &FullBlock, trie: Trie, index: usize) -> Result<InclusionProof> {
+    match trie.prove_index(index) {
+        Some(nodes) => Ok(InclusionProof {
+            header: rlp::encode(&block.header()).into(),
+            index: index as u64,
+            nodes: nodes.into_iter().map(Bytes).collect(),
+        }),
+        None => Err(Error::EthTxNotInBlock),
+    }
+}
+
+/// Build the proof of the transaction at `index` of the verified block.
+pub fn prove_tx(block: &FullBlock, index: usize) -> Result<InclusionProof> {
+    let encoded = block.transactions.iter().map(encode_tx).collect::<Option<Vec<_>>>();
+    match encoded {
+        Some(encoded) => prove(block, Trie::ordered(encoded), index),
+        None => Err(Error::InvalidEthBlock),
+    }
+}
+
+/// Build the proof of the receipt at `index`, the receipts must have been verified by `verify_receipts`.
+pub fn prove_receipt(block: &FullBlock, receipts: &[Receipt], index: usize) -> Result<InclusionProof> {
+    prove(block, Trie::ordered(receipts.iter().map(Receipt::encoded)), index)
+}
+
+/// Rebuild the header from the block and check its hash is the block hash.
+pub fn verify_header(block: &FullBlock) -> Result<Header> {
+    let hash = match block.hash {
+        Some(hash) => hash,
+        None => {
+            warn!("Cannot verify the pending block");
+            return Err(Error::InvalidEthBlock);
+        }
+    };
+    let header = block.header();
+    if header.hash() != hash {
+        warn!("Ethereum block {:?} has header hash {:?}", hash, header.hash());
+        return Err(Error::InvalidEthBlock);
+    }
+    Ok(header)
+}
+
+/// The consensus encoding of the transaction.
+///
+/// The known types are encoded from the fields, so that the checked sender, data and signature
+/// are the ones proven in the block. The raw transaction is used for the types unknown to `TxType`,
+/// `None` if there is no raw transaction.
+pub fn encode_tx(tx: &FullTransaction) -> Option<Vec<u8>> {
+    if tx.is_known_type() {
+        Some(UnverifiedTransaction::from(tx.clone()).encoded())
+    } else {
+        tx.raw.as_ref().map(|raw| raw.0.clone())
+    }
+}
+
+/// Check each transaction is encoded to its hash, and the transactions root of the block.
+///
+/// The transactions of the types unknown to `TxType` must have the raw transaction, see `fetch_block`.
+pub fn verify_transactions(block: &FullBlock) -> Result<()> {
+    let mut encoded = Vec::with_capacity(block.transactions.len());
+    for tx in &block.transactions {
+        let raw = match encode_tx(tx) {
+            Some(raw) => raw,
+            None => {
+                warn!("Ethereum tx {:?} has unknown type {:?} and no raw tx", tx.hash, tx.tx_type);
+                return Err(Error::InvalidEthBlock);
+            }
+        };
+        if keccak(&raw) != tx.hash {
+            warn!("Ethereum tx {:?} is encoded to hash {:?}", tx.hash, keccak(&raw));
+            return Err(Error::InvalidEthBlock);
+        }
+        encoded.push(raw);
+    }
+    let root = ordered_trie_root(encoded);
+    if root != block.transactions_root {
+        warn!("Ethereum block {:?} has transactions root {:?}", block.hash, root);
+        return Err(Error::InvalidEthBlock);
+    }
+    Ok(())
+}
+
+/// Check the receipts of all the transactions of the block against the receipts root.
+pub fn verify_receipts(block: &FullBlock, receipts: &[Receipt]) -> Result<()> {
+    if receipts.len() != block.transactions.len() {
+        warn!("Ethereum block {:?} has {} receipts", block.hash, receipts.len());
+        return Err(Error::InvalidEthBlock);
+    }
+    for (tx, receipt) in block.transactions.iter().zip(receipts) {
+        if tx.hash != receipt.transaction_hash {
+            warn!("Receipt of tx {:?} is not in the order of the block", receipt.transaction_hash);
+            return Err(Error::InvalidEthBlock);
+        }
+    }
+    let root = ordered_trie_root(receipts.iter().map(Receipt::encoded));
+    if root != block.receipts_root {
+        warn!("Ethereum block {:?} has receipts root {:?}", block.hash, root);
+        return Err(Error::InvalidEthBlock);
+    }
+    Ok(())
+}
+
+/// Verify the block, and check the transaction is included in it with the same content.
+///
+/// Return the index of the transaction in the block.
+pub fn verify_inclusion(block: &FullBlock, tx: &FullTransaction) -> Result<usize> {
+    verify_header(block)?;
+    verify_transactions(block)?;
+    if tx.block_hash != block.hash || encode_tx(tx).map(keccak) != Some(tx.hash) {
+        warn!("Ethereum tx {:?} does not belong to block {:?}", tx.hash, block.hash);
+        return Err(Error::EthTxNotInBlock);
+    }
+    match block.transactions.iter().position(|included| included.hash == tx.hash) {
+        Some(index) => Ok(index),
+        None => {
+            warn!("Ethereum tx {:?} is not included in block {:?}", tx.hash, block.hash);
+            Err(Error::EthTxNotInBlock)
+        }
+    }
+}
+
+/// Fetch the block from the source, with the raw transactions of the types unknown to `TxType`,
+/// so that a new transaction type in the block never fails the verification of the others.
+pub fn fetch_block<S: TxSource>(source: &S, number: u64) -> Result<FullBlock> {
+    let mut block = source.get_block_by_number(number)?;
+    for tx in block.transactions.iter_mut() {
+        if tx.raw.is_none() && !tx.is_known_type() {
+            debug!("Fetch the raw Ethereum tx {:?} of type {:?}", tx.hash, tx.tx_type);
+            tx.raw = Some(source.get_raw_tx(tx.hash)?);
+        }
+    }
+    Ok(block)
+}
+
+/// Fetch the block of the transaction from the source and verify the inclusion.
+///
+/// Return the proof of the transaction, which is stored with the mapping record.
+pub fn verify_tx<S: TxSource>(source: &S, tx: &FullTransaction) -> Result<InclusionProof> {
+    let number = match tx.block_number {
+        Some(number) => number.as_u64(),
+        None => return Err(Error::EthTxNotInBlock),
+    };
+    let block = fetch_block(source, number)?;
+    let index = verify_inclusion(&block, tx)?;
+    prove_tx(&block, index)
+}
+
+/// Fetch the receipts of the block of the transaction unless `receipts` has them, check them against
+/// the receipts root, and check the receipt of the transaction is the one proven in the block.
+///
+/// Return the proof of the receipt, which is stored with the mapping record.
+pub fn verify_tx_receipt<S: TxSource>(
+    source: &S,
+    receipts: &ReceiptCache,
+    tx: &FullTransaction,
+    receipt: &Receipt,
+) -> Result<InclusionProof> {
+    let number = match tx.block_number {
+        Some(number) => number.as_u64(),
+        None => return Err(Error::EthTxNotInBlock),
+    };
+    let block = source.get_block_by_number(number)?;
+    let block_hash = verify_header(&block)?.hash();
+    let index = match block.transactions.iter().position(|included| included.hash == tx.hash) {
+        Some(index) if tx.block_hash == block.hash => index,
+        _ => {
+            warn!("Ethereum tx {:?} is not included in block {:?}", tx.hash, block.hash);
+            return Err(Error::EthTxNotInBlock);
+        }
+    };
+    let proof = receipts.with_block(source, &block, block_hash, |receipts| prove_receipt(&block, receipts, index))?;
+    proof.verify_receipt(&block_hash, receipt)?;
+    Ok(proof)
+}
+
+/// The receipts of the recent blocks verified against their receipts roots,
+/// so that the mappings of the same block fetch its receipts once.
+#[derive(Debug, Default)]
+pub struct ReceiptCache {
+    blocks: RefCell<HashMap<H256, Vec<Receipt>>>,
+}
+
+impl ReceiptCache {
+    /// The number of blocks kept, the scanner and the hash files usually check the mappings block by block.
+    const MAX_BLOCKS: usize = 16;
+
+    /// Call `f` with the verified receipts of the block, fetched unless cached by the block hash.
+    fn with_block<S, T, F>(&self, source: &S, block: &FullBlock, block_hash: H256, f: F) -> Result<T>
+    where
+        S: TxSource,
+        F: FnOnce(&[Receipt]) -> Result<T>,
+    {
+        if let Some(receipts) = self.blocks.borrow().get(&block_hash) {
+            return f(receipts);
+        }
+        let receipts = block
+            .transactions
+            .iter()
+            .map(|included| source.get_receipt(included.hash))
+            .collect::<Result<Vec<_>>>()?;
+        verify_receipts(block, &receipts)?;
+        let result = f(&receipts);
+        let mut blocks = self.blocks.borrow_mut();
+        if blocks.len() >= Self::MAX_BLOCKS {
+            blocks.clear();
+        }
+        blocks.insert(block_hash, receipts);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::FixtureSource;
+    use crate::types::{H256, U256};
+    use hex_literal::hex;
+    use std::cell::Cell;
+    use std::path::PathBuf;
+
+    fn fixtures() -> FixtureSource {
+        FixtureSource::new(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures"))
+    }
+
+    fn genesis() -> FullBlock {
+        fixtures().get_block_by_number(0).unwrap()
+    }
+
+    #[test]
+    fn test_verify_genesis_header() {
+        let mut block = genesis();
+        let header = verify_header(&block).unwrap();
+        assert_eq!(
+            header.hash(),
+            H256::from(hex!("d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3"))
+        );
+        verify_transactions(&block).unwrap();
+        verify_receipts(&block, &[]).unwrap();
+
+        block.difficulty = U256::from(1);
+        assert!(verify_header(&block).is_err());
+    }
+
+    #[test]
+    fn test_verify_inclusion() {
+        // EIP-155 example tx
+        let tx: FullTransaction = serde_json::from_str(
+            r#"{
+                "blockHash": "0xd4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3",
+                "blockNumber": "0x0",
+                "from": "0x9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f",
+                "gas": "0x5208",
+                "gasPrice": "0x4a817c800",
+                "hash": "0x33469b22e9f636356c4160a87eb19df52b7412e8eac32a4a55ffe88ea8350788",
+                "input": "0x",
+                "nonce": "0x9",
+                "to": "0x3535353535353535353535353535353535353535",
+                "transactionIndex": "0x0",
+                "value": "0xde0b6b3a7640000",
+                "v": "0x25",
+                "r": "0x28ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276",
+                "s": "0x67cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83"
+            }"#,
+        )
+        .unwrap();
+
+        // The genesis has no transactions.
+        let mut block = genesis();
+        match verify_inclusion(&block, &tx) {
+            Err(Error::EthTxNotInBlock) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        // The transactions root does not match the header.
+        block.transactions.push(tx.clone());
+        match verify_inclusion(&block, &tx) {
+            Err(Error::InvalidEthBlock) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        // The tx content is modified.
+        block.transactions_root = ordered_trie_root(encode_tx(&tx));
+        block.hash = Some(block.header().hash());
+        let modified = FullTransaction {
+            value: 1.into(),
+            block_hash: block.hash,
+            ..tx.clone()
+        };
+        match verify_inclusion(&block, &modified) {
+            Err(Error::EthTxNotInBlock) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        // The raw tx of a known type never stands for the modified fields.
+        let forged = FullTransaction {
+            raw: encode_tx(&tx).map(Bytes),
+            ..modified
+        };
+        match verify_inclusion(&block, &forged) {
+            Err(Error::EthTxNotInBlock) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let tx = FullTransaction {
+            block_hash: block.hash,
+            ..tx
+        };
+        assert_eq!(verify_inclusion(&block, &tx).unwrap(), 0);
+
+        let block_hash = block.hash.unwrap();
+        let proof = prove_tx(&block, 0).unwrap();
+        proof.verify_tx(&block_hash, &tx.hash).unwrap();
+        assert!(proof.verify_tx(&block_hash, &H256::zero()).is_err());
+        assert!(proof.verify_tx(&genesis().hash.unwrap(), &tx.hash).is_err());
+        assert!(prove_tx(&block, 1).is_err());
+
+        // the proof survives the ledger
+        let json = serde_json::to_string(&proof).unwrap();
+        assert_eq!(serde_json::from_str::<InclusionProof>(&json).unwrap(), proof);
+    }
+
+    #[test]
+    fn test_verify_typed_block() {
+        // blob and set-code transactions before the mapping tx
+        let block = fixtures().get_block_by_number(1).unwrap();
+        assert_eq!(block.transactions[0].tx_type, Some(3.into()));
+        assert_eq!(block.transactions[1].tx_type, Some(4.into()));
+        verify_header(&block).unwrap();
+        verify_transactions(&block).unwrap();
+
+        let tx = block.transactions[2].clone();
+        assert_eq!(verify_inclusion(&block, &tx).unwrap(), 2);
+        let proof = verify_tx(&fixtures(), &tx).unwrap();
+        assert_eq!(proof.index, 2);
+        assert_eq!(proof, prove_tx(&block, 2).unwrap());
+        prove_tx(&block, 2).unwrap().verify_tx(&block.hash.unwrap(), &tx.hash).unwrap();
+    }
+
+    #[test]
+    fn test_unknown_tx_type() {
+        let block = fixtures().get_block_by_number(2).unwrap();
+        let tx = block.transactions[1].clone();
+        match verify_inclusion(&block, &tx) {
+            Err(Error::InvalidEthBlock) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        // the opaque tx is hashed from its raw encoding
+        let block = fetch_block(&fixtures(), 2).unwrap();
+        assert!(block.transactions[0].raw.is_some());
+        verify_transactions(&block).unwrap();
+        assert_eq!(verify_inclusion(&block, &tx).unwrap(), 1);
+        assert_eq!(verify_tx(&fixtures(), &tx).unwrap().index, 1);
+    }
+
+    #[test]
+    fn test_prove_receipt() {
+        let mut block = genesis();
+        let receipts = (0..3u64)
+            .map(|i| Receipt {
+                transaction_hash: H256::from_low_u64_be(i),
+                cumulative_gas_used: (21000 * (i + 1)).into(),
+                status: Some(1.into()),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+        block.receipts_root = ordered_trie_root(receipts.iter().map(Receipt::encoded));
+        let block_hash = block.header().hash();
+
+        let proof = prove_receipt(&block, &receipts, 2).unwrap();
+        proof.verify_receipt(&block_hash, &receipts[2]).unwrap();
+        assert!(proof.verify_receipt(&block_hash, &receipts[1]).is_err());
+    }
+
+    #[test]
+    fn test_verify_tx_receipt() {
+        let source = fixtures();
+        let hash = H256::from(hex!("ece0a27a08d6a1765d2331e596a42da43bbb1acb2293fc818d0259a56b0a0166"));
+        let tx = source.get_tx_by_hash(hash).unwrap();
+        let receipt = source.get_receipt(hash).unwrap();
+        let receipts = ReceiptCache::default();
+        let proof = verify_tx_receipt(&source, &receipts, &tx, &receipt).unwrap();
+        proof.verify_receipt(&tx.block_hash.unwrap(), &receipt).unwrap();
+
+        // the receipt of another tx of the block
+        let mut other = source.get_receipt(source.get_block_by_number(1).unwrap().transactions[0].hash).unwrap();
+        match verify_tx_receipt(&source, &receipts, &tx, &other) {
+            Err(Error::InvalidEthProof) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        other.transaction_hash = hash;
+        assert!(verify_tx_receipt(&source, &receipts, &tx, &other).is_err());
+    }
+
+    /// The fixture chain counting the fetched receipts.
+    struct CountReceipts {
+        fixtures: FixtureSource,
+        fetched: Cell<usize>,
+    }
+
+    impl TxSource for CountReceipts {
+        fn get_block_number(&self) -> Result<u64> {
+            self.fixtures.get_block_number()
+        }
+
+        fn get_tx_by_hash(&self, hash: H256) -> Result<FullTransaction> {
+            self.fixtures.get_tx_by_hash(hash)
+        }
+
+        fn get_block_by_number(&self, number: u64) -> Result<FullBlock> {
+            self.fixtures.get_block_by_number(number)
+        }
+
+        fn get_receipt(&self, hash: H256) -> Result<Receipt> {
+            self.fetched.set(self.fetched.get() + 1);
+            self.fixtures.get_receipt(hash)
+        }
+    }
+
+    #[test]
+    fn test_receipt_cache() {
+        let source = CountReceipts {
+            fixtures: fixtures(),
+            fetched: Cell::new(0),
+        };
+        let hash = H256::from(hex!("ece0a27a08d6a1765d2331e596a42da43bbb1acb2293fc818d0259a56b0a0166"));
+        let tx = source.get_tx_by_hash(hash).unwrap();
+        let receipt = source.fixtures.get_receipt(hash).unwrap();
+        let included = source.get_block_by_number(1).unwrap().transactions.len();
+
+        let receipts = ReceiptCache::default();
+        verify_tx_receipt(&source, &receipts, &tx, &receipt).unwrap();
+        assert_eq!(source.fetched.get(), included);
+        // the receipts of the same block are fetched once
+        verify_tx_receipt(&source, &receipts, &tx, &receipt).unwrap();
+        assert_eq!(source.fetched.get(), included);
+        // but again by another run
+        verify_tx_receipt(&source, &ReceiptCache::default(), &tx, &receipt).unwrap();
+        assert_eq!(source.fetched.get(), 2 * included);
+    }
+}